@@ -0,0 +1,170 @@
+use block::Block;
+use blocks::{self, create_block};
+use chan::Sender;
+use config::{Config, SharedBlockConfig};
+use errors::*;
+use input::I3BarEvent;
+use logging;
+use scheduler::{Clock, Task, UpdateScheduler};
+use signals::Signal;
+use std::collections::HashMap;
+use std::io::Write;
+use std::ops::DerefMut;
+use stats;
+use std::time::Duration;
+use theme_switcher::{ThemeRequest, ThemeSwitcher};
+use themes::SharedTheme;
+use util;
+
+/// Everything the main loop reacts to.
+pub enum BarEvent {
+    Click(I3BarEvent),
+    UpdateRequest(Task),
+    ThemeRequest(ThemeRequest),
+    Signal(Signal),
+    /// The time to the next scheduled update has passed.
+    ScheduledUpdate,
+}
+
+/// Creates the configured blocks. Every other block gets the alternating theme,
+/// blocks merged with their successor share its theme.
+pub fn create_blocks(config: &Config, alternating_theme: &SharedTheme, tx_update_requests: &Sender<Task>) -> Result<Vec<Box<Block>>> {
+    let mut config_alternating_tint = config.clone();
+    config_alternating_tint.theme = alternating_theme.clone();
+
+    let mut blocks: Vec<Box<Block>> = Vec::new();
+    let mut missing_icons: Vec<String> = Vec::new();
+    let mut alternator = false;
+    for &(ref block_name, ref shared, ref block_config) in &config.blocks {
+        let mut shared_config = if alternator {
+            config_alternating_tint.clone()
+        } else {
+            config.clone()
+        };
+        shared_config.icons.extend(shared.icons_overrides.clone());
+
+        for icon in blocks::referenced_icons(block_name, block_config) {
+            if !shared_config.icons.contains_key(&icon) {
+                missing_icons.push(format!("{} ({})", icon, block_name));
+            }
+        }
+
+        let block = create_block(
+            block_name,
+            block_config.clone(),
+            shared_config,
+            tx_update_requests.clone(),
+        )?;
+        stats::register(block.id(), block_name);
+        blocks.push(block);
+        // Merged blocks share a background, so they share a tint as well
+        if !shared.merge_with_next {
+            alternator = !alternator;
+        }
+    }
+
+    if !missing_icons.is_empty() {
+        // Blocks of the same kind miss the same icons
+        missing_icons.sort();
+        missing_icons.dedup();
+        warn!("icons", "icons missing from the icon set: {}", missing_icons.join(", "));
+    }
+
+    Ok(blocks)
+}
+
+/// The state of the main loop, independent of where its events come from and
+/// where the frames go to.
+pub struct Bar<'a> {
+    order: Vec<(String, SharedBlockConfig)>,
+    block_map: HashMap<String, &'a mut Block>,
+    config: &'a Config,
+    scheduler: UpdateScheduler,
+    theme_switcher: ThemeSwitcher,
+    stats_dump: bool,
+}
+
+impl<'a> Bar<'a> {
+    pub fn new(blocks: &'a mut [Box<Block>], config: &'a Config, theme_switcher: ThemeSwitcher, clock: Box<Clock>) -> Self {
+        // We save the order of the blocks here,
+        // because they will be passed to an unordered HashMap
+        let order = blocks
+            .iter()
+            .zip(config.blocks.iter())
+            .map(|(x, &(_, ref shared, _))| (String::from(x.id()), shared.clone()))
+            .collect::<Vec<_>>();
+
+        let scheduler = UpdateScheduler::new(blocks, clock);
+
+        let mut block_map: HashMap<String, &mut Block> = HashMap::new();
+        for block in blocks.iter_mut() {
+            block_map.insert(String::from(block.id()), (*block).deref_mut());
+        }
+
+        Bar {
+            order,
+            block_map,
+            config,
+            scheduler,
+            theme_switcher,
+            stats_dump: false,
+        }
+    }
+
+    /// Dumps the statistics of the blocks to the log on `SIGUSR1`.
+    pub fn with_stats_dump(mut self, enabled: bool) -> Self {
+        self.stats_dump = enabled;
+        self
+    }
+
+    pub fn time_to_next_update(&self) -> Option<Duration> {
+        self.scheduler.time_to_next_update()
+    }
+
+    /// Reacts to `event`, writing a new frame to `out` if anything changed.
+    pub fn handle<W: Write>(&mut self, event: BarEvent, out: &mut W) -> Result<()> {
+        match event {
+            BarEvent::Click(event) => {
+                for (id, block) in &mut self.block_map {
+                    stats::record_click(id, || block.click(&event))?;
+                }
+                self.redraw(out)
+            }
+            BarEvent::UpdateRequest(request) => {
+                debug!("scheduler", "update requested by {}", request.id);
+                // Process immediately and forget
+                let block = self.block_map
+                    .get_mut(&request.id)
+                    .internal_error("scheduler", "could not get required block")?;
+                stats::record_update(&request.id, || block.update())?;
+                self.redraw(out)
+            }
+            BarEvent::ThemeRequest(request) => {
+                if self.theme_switcher.apply(request)? {
+                    self.redraw(out)
+                } else {
+                    Ok(())
+                }
+            }
+            // SIGUSR2 toggles between the dark and the light theme
+            BarEvent::Signal(Signal::SIGUSR2) => self.handle(BarEvent::ThemeRequest(ThemeRequest::Toggle), out),
+            BarEvent::Signal(Signal::SIGUSR1) => {
+                if self.stats_dump {
+                    logging::dump("stats", &stats::report());
+                }
+                Ok(())
+            }
+            BarEvent::Signal(_) => Ok(()),
+            BarEvent::ScheduledUpdate => {
+                self.scheduler.do_scheduled_updates(&mut self.block_map)?;
+
+                // redraw the blocks, state changed
+                self.redraw(out)
+            }
+        }
+    }
+
+    pub fn redraw<W: Write>(&self, out: &mut W) -> Result<()> {
+        util::print_blocks(out, &self.order, &self.block_map, self.config)
+    }
+}
@@ -18,10 +18,12 @@ use inotify::{EventMask, Inotify, WatchMask};
 use uuid::Uuid;
 
 use block::{Block, ConfigBlock};
-use config::Config;
+use config::{Config, FsRoots};
 use errors::*;
 use input::{I3BarEvent, MouseButton};
+use logging::spawn_thread;
 use scheduler::Task;
+use util::IconRamp;
 use widget::I3BarWidget;
 use widgets::button::ButtonWidget;
 
@@ -53,8 +55,8 @@ pub struct BacklitDevice {
 impl BacklitDevice {
     /// Use the default backlit device, i.e. the first one found in the
     /// `/sys/class/backlight` directory.
-    pub fn default() -> Result<Self> {
-        let devices = Path::new("/sys/class/backlight")
+    pub fn default(roots: &FsRoots) -> Result<Self> {
+        let devices = roots.sysfs_path("class/backlight")
                            .read_dir() // Iterate over entries in the directory.
                            .block_error("backlight",
                                         "Failed to read backlight device directory")?;
@@ -84,8 +86,8 @@ impl BacklitDevice {
 
     /// Use the backlit device `device`. Returns an error if a directory for
     /// that device is not found.
-    pub fn from_device(device: String) -> Result<Self> {
-        let device_path = Path::new("/sys/class/backlight").join(device);
+    pub fn from_device(device: String, roots: &FsRoots) -> Result<Self> {
+        let device_path = roots.sysfs_path("class/backlight").join(device);
         if !device_path.exists() {
             return Err(BlockError(
                 "backlight".to_string(),
@@ -149,6 +151,7 @@ pub struct Backlight {
     output: ButtonWidget,
     device: BacklitDevice,
     step_width: u64,
+    icon_ramp: IconRamp,
 }
 
 /// Configuration for the [`Backlight`](./struct.Backlight.html) block.
@@ -162,6 +165,10 @@ pub struct BacklightConfig {
     /// The steps brightness is in/decreased for the selected screen (When greater than 50 it gets limited to 50)
     #[serde(default = "BacklightConfig::default_step_width")]
     pub step_width: u64,
+
+    /// Icons shown from the lowest to the highest brightness.
+    #[serde(default = "BacklightConfig::default_icon_ramp")]
+    pub icon_ramp: IconRamp,
 }
 
 impl BacklightConfig {
@@ -172,6 +179,16 @@ impl BacklightConfig {
     fn default_step_width() -> u64 {
         5
     }
+
+    fn default_icon_ramp() -> IconRamp {
+        IconRamp::new(&[
+            "backlight_empty",
+            "backlight_partial1",
+            "backlight_partial2",
+            "backlight_partial3",
+            "backlight_full",
+        ])
+    }
 }
 
 impl ConfigBlock for Backlight {
@@ -179,10 +196,12 @@ impl ConfigBlock for Backlight {
 
     fn new(block_config: Self::Config, config: Config, tx_update_request: Sender<Task>) -> Result<Self> {
         let device = match block_config.device {
-            Some(path) => BacklitDevice::from_device(path),
-            None => BacklitDevice::default(),
+            Some(path) => BacklitDevice::from_device(path, &config.roots),
+            None => BacklitDevice::default(&config.roots),
         }?;
 
+        block_config.icon_ramp.validate("backlight")?;
+
         let id = Uuid::new_v4().simple().to_string();
         let brightness_file = device.brightness_file();
 
@@ -191,21 +210,22 @@ impl ConfigBlock for Backlight {
             id: id.clone(),
             device,
             step_width: block_config.step_width,
+            icon_ramp: block_config.icon_ramp,
         };
 
         // Spin up a thread to watch for changes to the brightness file for the
         // device, and schedule an update if needed.
-        thread::spawn(move || {
-            let mut notify = Inotify::init().expect("Failed to start inotify");
+        spawn_thread("backlight", move || {
+            let mut notify = Inotify::init().block_error("backlight", "failed to start inotify")?;
             notify
                 .add_watch(brightness_file, WatchMask::MODIFY)
-                .expect("Failed to watch brightness file");
+                .block_error("backlight", "failed to watch brightness file")?;
 
             let mut buffer = [0; 1024];
             loop {
-                let mut events = notify.read_events_blocking(&mut buffer).expect(
-                    "Error while reading inotify events",
-                );
+                let mut events = notify
+                    .read_events_blocking(&mut buffer)
+                    .block_error("backlight", "failed to read inotify events")?;
 
                 if events.any(|event| event.mask.contains(EventMask::MODIFY)) {
                     tx_update_request.send(Task {
@@ -227,13 +247,7 @@ impl Block for Backlight {
     fn update(&mut self) -> Result<Option<Duration>> {
         let brightness = self.device.brightness()?;
         self.output.set_text(format!("{}%", brightness));
-        match brightness {
-            0...19 => self.output.set_icon("backlight_empty"),
-            20...39 => self.output.set_icon("backlight_partial1"),
-            40...59 => self.output.set_icon("backlight_partial2"),
-            60...79 => self.output.set_icon("backlight_partial3"),
-            _ => self.output.set_icon("backlight_full"),
-        }
+        self.output.set_icon(self.icon_ramp.icon(brightness));
         Ok(None)
     }
 
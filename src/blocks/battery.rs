@@ -2,31 +2,37 @@
 //!
 //! This module contains the [`Battery`](./struct.Battery.html) block, which can
 //! display the status, capacity, and time remaining for (dis)charge for an
-//! internal power supply.
+//! internal power supply, or for several of them combined.
 
-use std::path::{Path, PathBuf};
-use util::FormatTemplate;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+use util::{FormatTemplate, IconRamp};
 use std::time::{Duration, Instant};
-use std::thread;
 
 use chan::Sender;
 use blocks::dbus;
 use uuid::Uuid;
 
 use block::{Block, ConfigBlock};
-use config::Config;
-use de::deserialize_duration;
+use config::{Config, FsRoots};
+use de::{deserialize_duration, deserialize_string_or_list};
 use errors::*;
+use dbus_dispatcher::{self, Bus, MatchRule};
+use input::{I3BarEvent, MouseButton};
+use listener::DISCONNECTED;
+use logging::spawn_thread;
 use scheduler::Task;
+use stats;
 use util::read_file;
 use widget::{I3BarWidget, State};
-use widgets::text::TextWidget;
+use widgets::button::ButtonWidget;
 
 /// A battery device can be queried for a few properties relevant to the user.
 pub trait BatteryDevice {
     /// Query the device status, one of `"Full"`, `"Charging"`, `"Discharging"`,
-    /// or `"Unknown"`. Thinkpad batteries also report "`Not charging`", which
-    /// for our purposes should be treated as equivalent to full.
+    /// or `"Unknown"`. Thinkpad batteries also report "`Not charging`" when
+    /// they stop below full, e.g. at a charge threshold.
     fn status(&self) -> Result<String>;
 
     /// Query the device's current capacity, as a percent.
@@ -38,6 +44,34 @@ pub trait BatteryDevice {
 
     /// Query the current power consumption, in uW.
     fn power_consumption(&self) -> Result<u64>;
+
+    /// Query the energy stored in the device and the energy it stores when
+    /// full, in uWh.
+    fn energy(&self) -> Result<(u64, u64)>;
+
+    /// Query how much the device stores when full compared to when it was
+    /// new, as a percent.
+    fn health(&self) -> Result<u64> {
+        Err(BlockError("battery".into(), "Device does not support reading health".into()))
+    }
+
+    /// Query the number of charge cycles of the device.
+    fn cycles(&self) -> Result<u64> {
+        Err(BlockError("battery".into(), "Device does not support reading cycles".into()))
+    }
+
+    /// Query the charge, as a percent, below which the device starts charging
+    /// and at which it stops.
+    fn charge_thresholds(&self) -> Result<(u64, u64)> {
+        Err(BlockError("battery".into(), "Device does not support charge thresholds".into()))
+    }
+
+    /// Set the charge thresholds, as a percent. Without `start`, the device
+    /// starts charging whenever it is below `end`. The thresholds may only
+    /// change later, when they are set in the background.
+    fn set_charge_thresholds(&self, _start: Option<u64>, _end: u64) -> Result<()> {
+        Err(BlockError("battery".into(), "Device does not support setting charge thresholds".into()))
+    }
 }
 
 /// Represents a physical power supply device, as known to sysfs.
@@ -45,14 +79,22 @@ pub struct PowerSupplyDevice {
     device_path: PathBuf,
     charge_full: Option<u64>,
     energy_full: Option<u64>,
+    charge_helper: Option<ChargeHelper>,
+}
+
+/// A command setting charge thresholds, and the block updated once it exits.
+struct ChargeHelper {
+    command: String,
+    id: String,
+    update_request: Sender<Task>,
 }
 
 impl PowerSupplyDevice {
     /// Use the power supply device `device`, as found in the
     /// `/sys/class/power_supply` directory. Raises an error if a directory for
     /// that device is not found.
-    pub fn from_device(device: &str) -> Result<Self> {
-        let device_path = Path::new("/sys/class/power_supply").join(device);
+    pub fn from_device(device: &str, roots: &FsRoots) -> Result<Self> {
+        let device_path = roots.sysfs_path("class/power_supply").join(device);
         if !device_path.exists() {
             return Err(BlockError(
                 "battery".to_string(),
@@ -85,8 +127,111 @@ impl PowerSupplyDevice {
             device_path,
             charge_full,
             energy_full,
+            charge_helper: None,
         })
     }
+
+    /// Set charge thresholds by running `helper` when the sysfs files are not
+    /// writable, e.g. because only root may write them. The block `id` is
+    /// updated when the helper exits.
+    pub fn with_charge_helper(mut self, helper: Option<String>, id: &str, update_request: &Sender<Task>) -> Self {
+        self.charge_helper = helper.map(|command| ChargeHelper {
+            command,
+            id: id.to_owned(),
+            update_request: update_request.clone(),
+        });
+        self
+    }
+
+    /// Write `value` into the sysfs file `file` of this device.
+    fn write_u64(&self, file: &str, value: u64) -> Result<()> {
+        OpenOptions::new()
+            .write(true)
+            .truncate(true)
+            .open(self.device_path.join(file))
+            .block_error("battery", &format!("Failed to open {} for writing", file))?
+            .write_fmt(format_args!("{}\n", value))
+            .block_error("battery", &format!("Failed to write into {}", file))
+    }
+
+    /// Run the charge helper as `<helper> <device> <end> [<start>]` in the
+    /// background, as it may wait for a password or take a while.
+    fn run_charge_helper(&self, helper: &ChargeHelper, start: Option<u64>, end: u64) -> Result<()> {
+        let device = self.device_path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut words = helper.command.split_whitespace();
+        let mut command = stats::command(words.next().block_error("battery", "charge_helper is empty")?);
+        command.block(&helper.id).args(words).arg(device).arg(end.to_string());
+        if let Some(start) = start {
+            command.arg(start.to_string());
+        }
+
+        let id = helper.id.clone();
+        let update_request = helper.update_request.clone();
+        spawn_thread("battery", move || {
+            let status = command.status().block_error("battery", "Failed to run charge_helper");
+            update_request.send(Task {
+                id,
+                update_time: Instant::now(),
+            });
+            let status = status?;
+            if !status.success() {
+                return Err(BlockError(
+                    "battery".to_string(),
+                    format!("charge_helper failed with {}", status),
+                ));
+            }
+            Ok(())
+        });
+        Ok(())
+    }
+
+    /// The batteries in the `/sys/class/power_supply` directory, by name.
+    pub fn batteries(roots: &FsRoots) -> Result<Vec<String>> {
+        let supplies = roots.sysfs_path("class/power_supply");
+        let mut batteries = Vec::new();
+        for entry in fs::read_dir(&supplies).block_error("battery", "failed to list power supply devices")? {
+            let path = entry.block_error("battery", "failed to list power supply devices")?.path();
+            // Other supplies are e.g. AC adapters ("Mains") or USB ports
+            if read_file("battery", &path.join("type")).ok().map_or(false, |t| t == "Battery") {
+                if let Some(name) = path.file_name() {
+                    batteries.push(name.to_string_lossy().into_owned());
+                }
+            }
+        }
+        batteries.sort();
+
+        if batteries.is_empty() {
+            return Err(BlockError(
+                "battery".to_string(),
+                format!("No batteries found in '{}'", supplies.to_string_lossy()),
+            ));
+        }
+        Ok(batteries)
+    }
+
+    /// Whether an AC adapter in the `/sys/class/power_supply` directory is
+    /// plugged in.
+    pub fn ac_online(roots: &FsRoots) -> Result<bool> {
+        let supplies = roots.sysfs_path("class/power_supply");
+        for entry in fs::read_dir(&supplies).block_error("battery", "failed to list power supply devices")? {
+            let path = entry.block_error("battery", "failed to list power supply devices")?.path();
+            if read_file("battery", &path.join("type")).ok().map_or(false, |t| t == "Mains")
+                && read_file("battery", &path.join("online")).ok().map_or(false, |online| online == "1")
+            {
+                return Ok(true);
+            }
+        }
+        Ok(false)
+    }
+
+    fn read_u64(&self, file: &str) -> Result<u64> {
+        read_file("battery", &self.device_path.join(file))?
+            .parse::<u64>()
+            .block_error("battery", &format!("failed to parse {}", file))
+    }
 }
 
 impl BatteryDevice for PowerSupplyDevice {
@@ -204,21 +349,92 @@ impl BatteryDevice for PowerSupplyDevice {
             ))
         }
     }
+
+    fn energy(&self) -> Result<(u64, u64)> {
+        if let Some(full) = self.energy_full {
+            Ok((self.read_u64("energy_now")?, full))
+        } else if let Some(full) = self.charge_full {
+            let now = self.read_u64("charge_now")?;
+            // Charges (in uAh) only compare to energies with the voltage
+            if self.device_path.join("voltage_min_design").exists() {
+                let voltage = self.read_u64("voltage_min_design")?;
+                Ok((now * voltage / 1_000_000, full * voltage / 1_000_000))
+            } else {
+                Ok((now, full))
+            }
+        } else {
+            Err(BlockError(
+                "battery".to_string(),
+                "Device does not support reading energy".to_string(),
+            ))
+        }
+    }
+
+    fn health(&self) -> Result<u64> {
+        let (full, design) = match (self.energy_full, self.charge_full) {
+            (Some(full), _) => (full, self.read_u64("energy_full_design")?),
+            (None, Some(full)) => (full, self.read_u64("charge_full_design")?),
+            (None, None) => return Err(BlockError(
+                "battery".to_string(),
+                "Device does not support reading health".to_string(),
+            )),
+        };
+        if design == 0 {
+            return Err(BlockError("battery".to_string(), "Device reports no design capacity".to_string()));
+        }
+        Ok(full * 100 / design)
+    }
+
+    fn cycles(&self) -> Result<u64> {
+        self.read_u64("cycle_count")
+    }
+
+    fn charge_thresholds(&self) -> Result<(u64, u64)> {
+        let end = self.read_u64("charge_control_end_threshold")?;
+        // Some devices only have the end threshold
+        let start = if self.device_path.join("charge_control_start_threshold").exists() {
+            self.read_u64("charge_control_start_threshold")?
+        } else {
+            0
+        };
+        Ok((start, end))
+    }
+
+    fn set_charge_thresholds(&self, start: Option<u64>, end: u64) -> Result<()> {
+        // The kernel rejects a start threshold above the end threshold, so
+        // which comes first depends on whether the thresholds go up or down
+        let raise = self.charge_thresholds().map(|(_, current)| end > current).unwrap_or(true);
+        // Without a start threshold, one left from before must not hold off charging
+        let has_start = self.device_path.join("charge_control_start_threshold").exists();
+        let write = || -> Result<()> {
+            if raise {
+                self.write_u64("charge_control_end_threshold", end)?;
+            }
+            if start.is_some() || has_start {
+                self.write_u64("charge_control_start_threshold", start.unwrap_or(0))?;
+            }
+            if !raise {
+                self.write_u64("charge_control_end_threshold", end)?;
+            }
+            Ok(())
+        };
+
+        match (write(), &self.charge_helper) {
+            (Ok(()), _) => Ok(()),
+            (Err(_), &Some(ref helper)) => self.run_charge_helper(helper, start, end),
+            (Err(error), &None) => Err(error),
+        }
+    }
 }
 
-fn get_upower_property(con: &dbus::Connection, device_path: &str, property: &str) -> Result<dbus::Message> {
-    let msg = dbus::Message::new_method_call(
+fn get_upower_property(device_path: &str, property: &str) -> Result<dbus::Message> {
+    dbus_dispatcher::get_property(
+        Bus::System,
         "org.freedesktop.UPower",
         device_path,
-        "org.freedesktop.DBus.Properties",
-        "Get",
-    ).block_error("battery", "Failed to create Dbus message.")?
-        .append2(
-            dbus::MessageItem::Str("org.freedesktop.UPower.Device".to_string()),
-            dbus::MessageItem::Str(property.to_string()),
-        );
-
-    con.send_with_reply_and_block(msg, 1000).block_error(
+        "org.freedesktop.UPower.Device",
+        property,
+    ).block_error(
         "battery",
         &format!(
             "Failed to retrieve UPower property '{}' from '{}' via Dbus.",
@@ -230,7 +446,6 @@ fn get_upower_property(con: &dbus::Connection, device_path: &str, property: &str
 /// Represents a battery known to UPower.
 pub struct UpowerDevice {
     device_path: String,
-    con: dbus::Connection,
 }
 
 impl UpowerDevice {
@@ -240,10 +455,7 @@ impl UpowerDevice {
     /// battery.
     pub fn from_device(device: &str) -> Result<Self> {
         let device_path = format!("/org/freedesktop/UPower/devices/battery_{}", device);
-        let con = dbus::Connection::get_private(dbus::BusType::System)
-            .block_error("battery", "Failed to establish D-Bus connection.")?;
-
-        let upower_type: dbus::arg::Variant<u32> = get_upower_property(&con, &device_path, "Type")?
+        let upower_type: dbus::arg::Variant<u32> = get_upower_property(&device_path, "Type")?
             .get1()
             .block_error("battery", "Failed to read UPower Type property.")?;
 
@@ -254,52 +466,39 @@ impl UpowerDevice {
                 "UPower device is not a battery.".into(),
             ));
         }
-        Ok(UpowerDevice {
-            device_path,
-            con,
-        })
+        Ok(UpowerDevice { device_path })
     }
 
-    /// Monitor UPower property changes in a separate thread and send updates
+    /// The composite device UPower presents for all batteries together.
+    pub fn display_device() -> Self {
+        UpowerDevice {
+            device_path: "/org/freedesktop/UPower/devices/DisplayDevice".to_owned(),
+        }
+    }
+
+    /// Monitor UPower property changes through the D-Bus dispatcher and send updates
     /// via the `update_request` channel.
-    pub fn monitor(&self, id: String, update_request: Sender<Task>) {
-        let path = self.device_path.clone();
-        thread::spawn(move || {
-            let con = dbus::Connection::get_private(dbus::BusType::System)
-                .expect("Failed to establish D-Bus connection.");
-            let rule = format!(
-                "type='signal',\
-                 path='{}',\
-                 interface='org.freedesktop.DBus.Properties',\
-                 member='PropertiesChanged'",
-                path
-            );
-
-            // First we're going to get an (irrelevant) NameAcquired event.
-            con.incoming(10_000).next();
-
-            con.add_match(&rule)
-                .expect("Failed to add D-Bus match rule.");
-
-            loop {
-                if con.incoming(10_000).next().is_some() {
-                    update_request.send(Task {
-                        id: id.clone(),
-                        update_time: Instant::now(),
-                    });
-                    // Avoid update spam.
-                    // TODO: Is this necessary?
-                    thread::sleep(Duration::from_millis(1000))
-                }
-            }
-        });
+    pub fn monitor(&self, id: String, update_request: Sender<Task>) -> Result<()> {
+        dbus_dispatcher::subscribe(
+            Bus::System,
+            MatchRule::new()
+                .with_path(&self.device_path)
+                .with_interface("org.freedesktop.DBus.Properties")
+                .with_member("PropertiesChanged"),
+            move |_| {
+                update_request.send(Task {
+                    id: id.clone(),
+                    update_time: Instant::now(),
+                })
+            },
+        )
     }
 }
 
 impl BatteryDevice for UpowerDevice {
     fn status(&self) -> Result<String> {
         let status: dbus::arg::Variant<u32> =
-            get_upower_property(&self.con, &self.device_path, "State")?
+            get_upower_property(&self.device_path, "State")?
                 .get1()
                 .block_error("battery", "Failed to read UPower State property.")?;
 
@@ -317,7 +516,7 @@ impl BatteryDevice for UpowerDevice {
 
     fn capacity(&self) -> Result<u64> {
         let capacity: dbus::arg::Variant<f64> =
-            get_upower_property(&self.con, &self.device_path, "Percentage")?
+            get_upower_property(&self.device_path, "Percentage")?
                 .get1()
                 .block_error("battery", "Failed to read UPower Percentage property.")?;
 
@@ -329,31 +528,257 @@ impl BatteryDevice for UpowerDevice {
     }
 
     fn time_remaining(&self) -> Result<u64> {
-        let time_to_empty: dbus::arg::Variant<i64> =
-            get_upower_property(&self.con, &self.device_path, "TimeToEmpty")?
+        let property = if self.status()? == "Charging" { "TimeToFull" } else { "TimeToEmpty" };
+        let time: dbus::arg::Variant<i64> =
+            get_upower_property(&self.device_path, property)?
                 .get1()
-                .block_error("battery", "Failed to read UPower TimeToEmpty property.")?;
-        Ok((time_to_empty.0 / 60) as u64)
+                .block_error("battery", &format!("Failed to read UPower {} property.", property))?;
+        Ok((time.0 / 60) as u64)
     }
 
     fn power_consumption(&self) -> Result<u64> {
         let energy_rate: dbus::arg::Variant<f64> =
-            get_upower_property(&self.con, &self.device_path, "EnergyRate")?
+            get_upower_property(&self.device_path, "EnergyRate")?
                 .get1()
                 .block_error("battery", "Failed to read UPower EnergyRate property.")?;
         // FIXME: Might want to make the interface send Watts instead.
         Ok((energy_rate.0 * 1_000_000.0) as u64)
     }
+
+    fn energy(&self) -> Result<(u64, u64)> {
+        let energy: dbus::arg::Variant<f64> =
+            get_upower_property(&self.device_path, "Energy")?
+                .get1()
+                .block_error("battery", "Failed to read UPower Energy property.")?;
+        let energy_full: dbus::arg::Variant<f64> =
+            get_upower_property(&self.device_path, "EnergyFull")?
+                .get1()
+                .block_error("battery", "Failed to read UPower EnergyFull property.")?;
+        Ok(((energy.0 * 1_000_000.0) as u64, (energy_full.0 * 1_000_000.0) as u64))
+    }
+
+    fn health(&self) -> Result<u64> {
+        let capacity: dbus::arg::Variant<f64> =
+            get_upower_property(&self.device_path, "Capacity")?
+                .get1()
+                .block_error("battery", "Failed to read UPower Capacity property.")?;
+        Ok(capacity.0.round() as u64)
+    }
+
+    fn cycles(&self) -> Result<u64> {
+        let cycles: dbus::arg::Variant<i32> =
+            get_upower_property(&self.device_path, "ChargeCycles")?
+                .get1()
+                .block_error("battery", "Failed to read UPower ChargeCycles property.")?;
+        // UPower reports -1 when the battery doesn't know
+        if cycles.0 < 0 {
+            return Err(BlockError("battery".into(), "UPower does not know the charge cycles.".into()));
+        }
+        Ok(cycles.0 as u64)
+    }
+
+    fn charge_thresholds(&self) -> Result<(u64, u64)> {
+        let start: dbus::arg::Variant<u32> =
+            get_upower_property(&self.device_path, "ChargeStartThreshold")?
+                .get1()
+                .block_error("battery", "Failed to read UPower ChargeStartThreshold property.")?;
+        let end: dbus::arg::Variant<u32> =
+            get_upower_property(&self.device_path, "ChargeEndThreshold")?
+                .get1()
+                .block_error("battery", "Failed to read UPower ChargeEndThreshold property.")?;
+        Ok((u64::from(start.0), u64::from(end.0)))
+    }
+}
+
+/// Whether UPower runs the system on AC power.
+fn upower_on_ac() -> Result<bool> {
+    let on_battery: dbus::arg::Variant<bool> = dbus_dispatcher::get_property(
+        Bus::System,
+        "org.freedesktop.UPower",
+        "/org/freedesktop/UPower",
+        "org.freedesktop.UPower",
+        "OnBattery",
+    ).block_error("battery", "Failed to retrieve UPower property 'OnBattery' via Dbus.")?
+        .get1()
+        .block_error("battery", "Failed to read UPower OnBattery property.")?;
+    Ok(!on_battery.0)
+}
+
+/// Several batteries shown as one, with their energy and power added up.
+pub struct BatteryGroup {
+    devices: Vec<Box<BatteryDevice>>,
+}
+
+impl BatteryGroup {
+    pub fn new(devices: Vec<Box<BatteryDevice>>) -> Self {
+        BatteryGroup { devices }
+    }
+
+    /// The capacity of each battery, as a percent.
+    pub fn capacities(&self) -> Vec<Result<u64>> {
+        self.devices.iter().map(|device| device.capacity()).collect()
+    }
+
+    /// The power of all batteries (in uW), if at least one reports it.
+    fn total_power(&self) -> Result<u64> {
+        let powers: Vec<u64> = self.devices
+            .iter()
+            .filter_map(|device| device.power_consumption().ok())
+            .collect();
+        if powers.is_empty() {
+            return Err(BlockError(
+                "battery".to_string(),
+                "No battery supports power consumption".to_string(),
+            ));
+        }
+        Ok(powers.iter().sum())
+    }
+}
+
+impl BatteryDevice for BatteryGroup {
+    fn status(&self) -> Result<String> {
+        if self.devices.len() == 1 {
+            return self.devices[0].status();
+        }
+
+        // One battery (dis)charging is what matters, while the others idle
+        let statuses = self.devices
+            .iter()
+            .map(|device| device.status())
+            .collect::<Result<Vec<String>>>()?;
+        for status in &["Charging", "Discharging"] {
+            if statuses.iter().any(|s| s == status) {
+                return Ok(status.to_string());
+            }
+        }
+        if statuses.iter().all(|s| s == "Full") {
+            return Ok("Full".to_string());
+        }
+        if statuses.iter().all(|s| s == "Full" || s == "Not charging") {
+            return Ok("Not charging".to_string());
+        }
+        Ok(statuses[0].clone())
+    }
+
+    fn capacity(&self) -> Result<u64> {
+        if self.devices.len() == 1 {
+            return self.devices[0].capacity();
+        }
+
+        match self.energy() {
+            Ok((_, 0)) | Err(_) => {
+                // Without energies, batteries count the same
+                let capacities = self.capacities().into_iter().collect::<Result<Vec<u64>>>()?;
+                Ok(capacities.iter().sum::<u64>() / capacities.len() as u64)
+            }
+            Ok((now, full)) => Ok(::std::cmp::min(100, now * 100 / full)),
+        }
+    }
+
+    fn time_remaining(&self) -> Result<u64> {
+        if self.devices.len() == 1 {
+            return self.devices[0].time_remaining();
+        }
+
+        let (now, full) = self.energy()?;
+        let power = self.total_power()?;
+        if power == 0 {
+            return Ok(0);
+        }
+        match self.status()?.as_str() {
+            "Discharging" => Ok(now * 60 / power),
+            "Charging" => Ok(full.saturating_sub(now) * 60 / power),
+            _ => Ok(0),
+        }
+    }
+
+    fn power_consumption(&self) -> Result<u64> {
+        if self.devices.len() == 1 {
+            return self.devices[0].power_consumption();
+        }
+        self.total_power()
+    }
+
+    fn health(&self) -> Result<u64> {
+        if self.devices.len() == 1 {
+            return self.devices[0].health();
+        }
+        let healths = self.devices
+            .iter()
+            .map(|device| device.health())
+            .collect::<Result<Vec<u64>>>()?;
+        Ok(healths.iter().sum::<u64>() / healths.len() as u64)
+    }
+
+    fn cycles(&self) -> Result<u64> {
+        // The most worn battery is the one to care about
+        let cycles = self.devices
+            .iter()
+            .map(|device| device.cycles())
+            .collect::<Result<Vec<u64>>>()?;
+        Ok(cycles.into_iter().max().unwrap_or(0))
+    }
+
+    fn charge_thresholds(&self) -> Result<(u64, u64)> {
+        // Batteries are usually configured alike, so the first one stands for all
+        self.devices[0].charge_thresholds()
+    }
+
+    fn set_charge_thresholds(&self, start: Option<u64>, end: u64) -> Result<()> {
+        for device in &self.devices {
+            device.set_charge_thresholds(start, end)?;
+        }
+        Ok(())
+    }
+
+    fn energy(&self) -> Result<(u64, u64)> {
+        let mut total = (0, 0);
+        for device in &self.devices {
+            let (now, full) = device.energy()?;
+            total = (total.0 + now, total.1 + full);
+        }
+        Ok(total)
+    }
 }
 
 /// A block for displaying information about an internal power supply.
 pub struct Battery {
-    output: TextWidget,
+    output: ButtonWidget,
     id: String,
     update_interval: Duration,
-    device: Box<BatteryDevice>,
+    device: BatteryGroup,
     format: FormatTemplate,
     upower: bool,
+    icon_ramp: IconRamp,
+    roots: FsRoots,
+    hidden: bool,
+    hide_when_full: bool,
+    hide_when_on_ac: bool,
+    critical: u64,
+    warning: u64,
+    good: u64,
+    charge_profiles: Vec<ChargeProfile>,
+}
+
+/// Charge thresholds to switch to from the bar.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ChargeProfile {
+    /// Shown as `{profile}` while the profile is active
+    pub name: String,
+
+    /// Charging starts below this percentage, if the device supports it
+    #[serde(default)]
+    pub start: Option<u64>,
+
+    /// Charging stops at this percentage
+    pub end: u64,
+}
+
+impl ChargeProfile {
+    fn matches(&self, (start, end): (u64, u64)) -> bool {
+        self.end == end && self.start.unwrap_or(0) == start
+    }
 }
 
 /// Configuration for the [`Battery`](./struct.Battery.html) block.
@@ -364,23 +789,56 @@ pub struct BatteryConfig {
     #[serde(default = "BatteryConfig::default_interval", deserialize_with = "deserialize_duration")]
     pub interval: Duration,
 
-    /// The internal power supply device in `/sys/class/power_supply/` to read
-    /// from.
-    #[serde(default = "BatteryConfig::default_device")]
-    pub device: String,
+    /// The internal power supply devices in `/sys/class/power_supply/` to read
+    /// from, or `"all"` for all batteries.
+    #[serde(default = "BatteryConfig::default_device", deserialize_with = "deserialize_string_or_list")]
+    pub device: Vec<String>,
 
     /// (DEPRECATED) Options for displaying battery information.
     #[serde()]
     pub show: Option<String>,
 
     /// Format string for displaying battery information.
-    /// placeholders: {percentage}, {time} and {power}
+    /// placeholders: {percentage}, {percentages}, {time}, {power}, {health},
+    /// {cycles}, {status}, {thresholds} and {profile}
     #[serde(default = "BatteryConfig::default_format")]
     pub format: String,
 
     /// Use UPower to monitor battery status and events.
     #[serde(default = "BatteryConfig::default_upower")]
     pub upower: bool,
+
+    /// Icons shown while discharging, from the lowest to the highest charge.
+    #[serde(default = "BatteryConfig::default_icon_ramp")]
+    pub icon_ramp: IconRamp,
+
+    /// Hide the block while the battery is full.
+    #[serde(default = "BatteryConfig::default_hide_when_full")]
+    pub hide_when_full: bool,
+
+    /// Hide the block while an AC adapter is plugged in.
+    #[serde(default = "BatteryConfig::default_hide_when_on_ac")]
+    pub hide_when_on_ac: bool,
+
+    /// The highest percentage shown as critical while discharging.
+    #[serde(default = "BatteryConfig::default_critical")]
+    pub critical: u64,
+
+    /// The highest percentage shown as a warning while discharging.
+    #[serde(default = "BatteryConfig::default_warning")]
+    pub warning: u64,
+
+    /// The lowest percentage shown as good while discharging.
+    #[serde(default = "BatteryConfig::default_good")]
+    pub good: u64,
+
+    /// Charge thresholds a click switches between, in turn.
+    #[serde(default = "BatteryConfig::default_charge_profiles")]
+    pub charge_profiles: Vec<ChargeProfile>,
+
+    /// Command setting charge thresholds when sysfs is not writable.
+    #[serde(default = "BatteryConfig::default_charge_helper")]
+    pub charge_helper: Option<String>,
 }
 
 impl BatteryConfig {
@@ -388,8 +846,8 @@ impl BatteryConfig {
         Duration::from_secs(10)
     }
 
-    fn default_device() -> String {
-        "BAT0".to_string()
+    fn default_device() -> Vec<String> {
+        vec!["BAT0".to_string()]
     }
 
     fn default_format() -> String {
@@ -399,6 +857,38 @@ impl BatteryConfig {
     fn default_upower() -> bool {
         false
     }
+
+    fn default_icon_ramp() -> IconRamp {
+        IconRamp::new(&["bat_discharging"])
+    }
+
+    fn default_hide_when_full() -> bool {
+        false
+    }
+
+    fn default_hide_when_on_ac() -> bool {
+        false
+    }
+
+    fn default_critical() -> u64 {
+        15
+    }
+
+    fn default_warning() -> u64 {
+        30
+    }
+
+    fn default_good() -> u64 {
+        61
+    }
+
+    fn default_charge_profiles() -> Vec<ChargeProfile> {
+        Vec::new()
+    }
+
+    fn default_charge_helper() -> Option<String> {
+        None
+    }
 }
 
 impl ConfigBlock for Battery {
@@ -420,37 +910,145 @@ impl ConfigBlock for Battery {
             },
             None => block_config.format
         };
+        block_config.icon_ramp.validate("battery")?;
+        if block_config.critical > block_config.warning || block_config.warning >= block_config.good {
+            return Err(BlockError(
+                "battery".into(),
+                "thresholds must satisfy critical <= warning < good".into(),
+            ));
+        }
+        for profile in &block_config.charge_profiles {
+            if profile.end > 100 || profile.start.map_or(false, |start| start >= profile.end) {
+                return Err(BlockError(
+                    "battery".into(),
+                    format!("charge profile '{}' must satisfy start < end <= 100", profile.name),
+                ));
+            }
+        }
 
         let id = Uuid::new_v4().simple().to_string();
-        let device: Box<BatteryDevice> = if block_config.upower {
-            let out = UpowerDevice::from_device(&block_config.device)?;
-            out.monitor(id.clone(), update_request);
-            Box::new(out)
+        let all = block_config.device.len() == 1 && block_config.device[0] == "all";
+        let mut devices: Vec<Box<BatteryDevice>> = Vec::new();
+        if block_config.upower {
+            // UPower already combines all batteries in its DisplayDevice
+            let upower_devices = if all || block_config.device == ["DisplayDevice"] {
+                vec![UpowerDevice::display_device()]
+            } else {
+                block_config
+                    .device
+                    .iter()
+                    .map(|device| UpowerDevice::from_device(device))
+                    .collect::<Result<Vec<UpowerDevice>>>()?
+            };
+            for device in upower_devices {
+                device.monitor(id.clone(), update_request.clone())?;
+                devices.push(Box::new(device));
+            }
         } else {
-            Box::new(PowerSupplyDevice::from_device(&block_config.device)?)
-
-        };
+            let names = if all {
+                PowerSupplyDevice::batteries(&config.roots)?
+            } else {
+                block_config.device
+            };
+            for name in names {
+                devices.push(Box::new(
+                    PowerSupplyDevice::from_device(&name, &config.roots)?
+                        .with_charge_helper(block_config.charge_helper.clone(), &id, &update_request),
+                ));
+            }
+        }
+        let device = BatteryGroup::new(devices);
 
         Ok(Battery {
             id,
             update_interval: block_config.interval,
-            output: TextWidget::new(config),
+            roots: config.roots.clone(),
+            output: ButtonWidget::new(config, &id),
             device,
             format: FormatTemplate::from_string(&format)?,
             upower: block_config.upower,
+            icon_ramp: block_config.icon_ramp,
+            hidden: false,
+            hide_when_full: block_config.hide_when_full,
+            hide_when_on_ac: block_config.hide_when_on_ac,
+            critical: block_config.critical,
+            warning: block_config.warning,
+            good: block_config.good,
+            charge_profiles: block_config.charge_profiles,
         })
     }
 }
 
+impl Battery {
+    fn on_ac(&self) -> Result<bool> {
+        if self.upower {
+            upower_on_ac()
+        } else {
+            PowerSupplyDevice::ac_online(&self.roots)
+        }
+    }
+
+    /// The name of the active charge profile.
+    fn profile(&self) -> String {
+        match self.device.charge_thresholds() {
+            Ok(thresholds) => self.charge_profiles
+                .iter()
+                .find(|profile| profile.matches(thresholds))
+                .map_or_else(|| format!("{}-{}", thresholds.0, thresholds.1), |profile| profile.name.clone()),
+            Err(_) => "×".into(),
+        }
+    }
+
+    /// Switches to the charge profile after the active one.
+    fn next_profile(&mut self) -> Result<()> {
+        if self.charge_profiles.is_empty() {
+            return Ok(());
+        }
+        let next = match self.device.charge_thresholds() {
+            Ok(thresholds) => self.charge_profiles
+                .iter()
+                .position(|profile| profile.matches(thresholds))
+                .map_or(0, |active| (active + 1) % self.charge_profiles.len()),
+            Err(_) => 0,
+        };
+        let profile = &self.charge_profiles[next];
+        self.device.set_charge_thresholds(profile.start, profile.end)
+    }
+
+    fn next_update(&self) -> Result<Option<Duration>> {
+        if self.upower {
+            Ok(None)
+        } else {
+            Ok(Some(self.update_interval))
+        }
+    }
+}
+
 impl Block for Battery {
     fn update(&mut self) -> Result<Option<Duration>> {
         // TODO: Maybe use dbus to immediately signal when the battery state changes.
 
+        if self.upower && !dbus_dispatcher::is_connected(Bus::System) {
+            self.output.set_icon("bat");
+            self.output.set_text(DISCONNECTED.to_owned());
+            self.output.set_state(State::Idle);
+            self.hidden = false;
+            return Ok(None);
+        }
+
         let status = self.device.status()?;
+        let full = status == "Full";
+
+        self.hidden = (self.hide_when_full && full) || (self.hide_when_on_ac && self.on_ac()?);
+        if self.hidden {
+            return self.next_update();
+        }
 
-        if status == "Full" || status == "Not charging" {
+        if full {
             self.output.set_icon("bat_full");
-            self.output.set_text("".to_string());
+            // With profiles, which one stopped charging is worth showing
+            let profile = if self.charge_profiles.is_empty() { String::new() } else { self.profile() };
+            self.output.set_text(profile);
             self.output.set_state(State::Good);
         } else {
             let capacity = self.device.capacity();
@@ -466,9 +1064,36 @@ impl Block for Battery {
                 Ok(power) => format!("{:.2}", power as f64 / 1000.0 / 1000.0),
                 Err(_) => "×".into(),
             };
+            let percentages = self.device
+                .capacities()
+                .into_iter()
+                .map(|capacity| match capacity {
+                    Ok(capacity) => format!("{}", capacity),
+                    Err(_) => "×".into(),
+                })
+                .collect::<Vec<String>>()
+                .join("/");
+            let health = match self.device.health() {
+                Ok(health) => format!("{}", health),
+                Err(_) => "×".into(),
+            };
+            let cycles = match self.device.cycles() {
+                Ok(cycles) => format!("{}", cycles),
+                Err(_) => "×".into(),
+            };
+            let thresholds = match self.device.charge_thresholds() {
+                Ok((start, end)) => format!("{}-{}", start, end),
+                Err(_) => "×".into(),
+            };
             let values = map!("{percentage}" => percentage,
+                              "{percentages}" => percentages,
                               "{time}" => time,
-                              "{power}" => power);
+                              "{power}" => power,
+                              "{health}" => health,
+                              "{cycles}" => cycles,
+                              "{status}" => status.clone(),
+                              "{thresholds}" => thresholds,
+                              "{profile}" => self.profile());
             self.output.set_text(self.format.render_static_str(&values)?);
 
             // Check if the battery is in charging mode and change the state to Good.
@@ -477,34 +1102,77 @@ impl Block for Battery {
                 "Charging" => { self.output.set_state(State::Good); },
                 _ =>
                     { self.output.set_state(match capacity {
-                    Ok(0...15) => State::Critical,
-                    Ok(16...30) => State::Warning,
-                    Ok(31...60) => State::Info,
-                    Ok(61...100) => State::Good,
+                    Ok(capacity) if capacity <= self.critical => State::Critical,
+                    Ok(capacity) if capacity <= self.warning => State::Warning,
+                    Ok(capacity) if capacity < self.good => State::Info,
+                    Ok(_) => State::Good,
                     _ => State::Warning,
                     });
                 }
             }
 
-            self.output.set_icon(match status.as_str() {
-                "Discharging" => "bat_discharging",
-                "Charging" => "bat_charging",
-                _ => "bat",
-            });
+            match (status.as_str(), capacity) {
+                ("Discharging", Ok(capacity)) => self.output.set_icon(self.icon_ramp.icon(capacity)),
+                ("Discharging", Err(_)) => self.output.set_icon("bat_discharging"),
+                ("Charging", _) => self.output.set_icon("bat_charging"),
+                _ => self.output.set_icon("bat"),
+            }
         }
 
-        if self.upower {
-            Ok(None)
-        } else {
-            Ok(Some(self.update_interval))
-        }
+        self.next_update()
     }
 
     fn view(&self) -> Vec<&I3BarWidget> {
+        if self.hidden {
+            return Vec::new();
+        }
         vec![&self.output]
     }
 
+    fn click(&mut self, event: &I3BarEvent) -> Result<()> {
+        if event.name.as_ref().map_or(false, |name| *name == self.id) {
+            if let MouseButton::Left = event.button {
+                // The thresholds are often only writable by root
+                if let Err(error) = self.next_profile() {
+                    warn!("battery", "failed to switch the charge profile: {:?}", error);
+                }
+                self.update()?;
+            }
+        }
+        Ok(())
+    }
+
     fn id(&self) -> &str {
         &self.id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use harness::writable_fixture;
+
+    #[test]
+    fn battery_writes_charge_thresholds() {
+        let roots = writable_fixture("laptop");
+        let battery = PowerSupplyDevice::from_device("BAT0", &roots).expect("no battery");
+        assert_eq!(battery.charge_thresholds().expect("no thresholds"), (75, 80));
+
+        battery.set_charge_thresholds(None, 100).expect("failed to raise thresholds");
+        assert_eq!(battery.charge_thresholds().expect("no thresholds"), (0, 100));
+
+        battery.set_charge_thresholds(Some(40), 60).expect("failed to lower thresholds");
+        assert_eq!(battery.charge_thresholds().expect("no thresholds"), (40, 60));
+    }
+
+    #[test]
+    fn battery_profiles_without_start_charge_from_empty() {
+        let full = ChargeProfile {
+            name: "full".to_owned(),
+            start: None,
+            end: 100,
+        };
+        assert!(full.matches((0, 100)));
+        assert!(!full.matches((75, 100)));
+    }
+}
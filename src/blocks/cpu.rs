@@ -3,7 +3,7 @@ use scheduler::Task;
 use std::time::Duration;
 
 use block::{Block, ConfigBlock};
-use config::Config;
+use config::{Config, FsRoots};
 use de::deserialize_duration;
 use errors::*;
 use widget::{I3BarWidget, State};
@@ -25,6 +25,7 @@ pub struct Cpu {
     minimum_warning: u64,
     minimum_critical: u64,
     frequency: bool,
+    roots: FsRoots,
 }
 
 #[derive(Deserialize, Debug, Default, Clone)]
@@ -78,6 +79,7 @@ impl ConfigBlock for Cpu {
 
     fn new(block_config: Self::Config, config: Config, _tx_update_request: Sender<Task>) -> Result<Self> {
         Ok(Cpu {
+            roots: config.roots.clone(),
             id: Uuid::new_v4().simple().to_string(),
             update_interval: block_config.interval,
             utilization: TextWidget::new(config).with_icon("cpu"),
@@ -93,12 +95,12 @@ impl ConfigBlock for Cpu {
 
 impl Block for Cpu {
     fn update(&mut self) -> Result<Option<Duration>> {
-        let f = File::open("/proc/stat").block_error("cpu", "Your system doesn't support /proc/stat")?;
+        let f = File::open(self.roots.procfs_path("stat")).block_error("cpu", "Your system doesn't support /proc/stat")?;
         let f = BufReader::new(f);
 
         let mut freq: f32 = 0.0;
         if self.frequency {
-            let freq_file = File::open("/proc/cpuinfo").block_error("cpu", "failed to read /proc/cpuinfo")?;
+            let freq_file = File::open(self.roots.procfs_path("cpuinfo")).block_error("cpu", "failed to read /proc/cpuinfo")?;
             let freq_file_content = BufReader::new(freq_file);
             let mut cores = 0;
             // read frequency of each cpu and calculate the average which we will display
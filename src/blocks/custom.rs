@@ -1,5 +1,5 @@
 use std::time::{Duration, Instant};
-use std::process::Command;
+use stats;
 use std::iter::{Cycle, Peekable};
 use std::vec;
 use std::env;
@@ -89,7 +89,7 @@ impl Block for Custom {
             .or_else(|| self.command.clone())
             .unwrap_or_else(|| "".to_owned());
 
-        let output = Command::new(env::var("SHELL").unwrap_or("sh".to_owned()))
+        let output = stats::command(env::var("SHELL").unwrap_or("sh".to_owned()))
             .args(&["-c", &command_str])
             .output()
             .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
@@ -116,7 +116,7 @@ impl Block for Custom {
         let mut update = false;
 
         if let Some(ref on_click) = self.on_click {
-            Command::new(env::var("SHELL").unwrap_or_else(|_|"sh".to_owned()))
+            stats::command(env::var("SHELL").unwrap_or_else(|_|"sh".to_owned()))
                     .args(&["-c", on_click]).output().ok();
             update = true;
         }
@@ -1,11 +1,12 @@
 use std::time::{Duration, Instant};
 use chan::Sender;
-use std::thread;
 use std::sync::{Arc, Mutex};
 
 use block::{Block, ConfigBlock};
 use config::Config;
 use errors::*;
+use i3_dispatcher::{self, Event, EventKind, I3Event};
+use listener::DISCONNECTED;
 use widgets::text::TextWidget;
 use widget::I3BarWidget;
 use scheduler::Task;
@@ -13,9 +14,6 @@ use scheduler::Task;
 use uuid::Uuid;
 
 extern crate i3ipc;
-use self::i3ipc::I3EventListener;
-use self::i3ipc::Subscription;
-use self::i3ipc::event::Event;
 use self::i3ipc::event::inner::{WindowChange, WorkspaceChange};
 
 pub struct FocusedWindow {
@@ -44,68 +42,52 @@ impl ConfigBlock for FocusedWindow {
 
     fn new(block_config: Self::Config, config: Config, tx: Sender<Task>) -> Result<Self> {
         let id = Uuid::new_v4().simple().to_string();
-        let id_clone = id.clone();
 
         let title_original = Arc::new(Mutex::new(String::from("")));
         let title = title_original.clone();
 
-        thread::spawn(move || {
-            // establish connection.
-            let mut listener = I3EventListener::connect().unwrap();
-
-            // subscribe to a couple events.
-            let subs = [Subscription::Window, Subscription::Workspace];
-            listener.subscribe(&subs).unwrap();
-
-            // handle them
-            for event in listener.listen() {
-                match event.unwrap() {
-                    Event::WindowEvent(e) => {
-                        match e.change {
-                            WindowChange::Focus => if let Some(name) = e.container.name {
-                                let mut title = title_original.lock().unwrap();
-                                *title = name;
-                                tx.send(Task {
-                                    id: id_clone.clone(),
-                                    update_time: Instant::now(),
-                                });
-                            },
-                            WindowChange::Title => if e.container.focused {
-                                if let Some(name) = e.container.name {
-                                    let mut title = title_original.lock().unwrap();
-                                    *title = name;
-                                    tx.send(Task {
-                                        id: id_clone.clone(),
-                                        update_time: Instant::now(),
-                                    });
-                                }
-                            },
-                            WindowChange::Close => if let Some(name) = e.container.name {
-                                let mut title = title_original.lock().unwrap();
-                                if name == *title {
-                                    *title = String::from("");
-                                    tx.send(Task {
-                                        id: id_clone.clone(),
-                                        update_time: Instant::now(),
-                                    });
-                                }
-                            },
-                            _ => {}
-                        };
-                    }
-                    Event::WorkspaceEvent(e) => {
-                        if let WorkspaceChange::Init = e.change {
-                            let mut title = title_original.lock().unwrap();
+        let update_id = id.clone();
+        let update = move || {
+            tx.send(Task {
+                id: update_id.clone(),
+                update_time: Instant::now(),
+            })
+        };
+
+        i3_dispatcher::subscribe(&[EventKind::Window, EventKind::Workspace], move |event| match event {
+            I3Event::Event(&Event::WindowEvent(ref e)) => {
+                match e.change {
+                    WindowChange::Focus => if let Some(ref name) = e.container.name {
+                        let mut title = title_original.lock().unwrap_or_else(|e| e.into_inner());
+                        *title = name.clone();
+                        update();
+                    },
+                    WindowChange::Title => if e.container.focused {
+                        if let Some(ref name) = e.container.name {
+                            let mut title = title_original.lock().unwrap_or_else(|e| e.into_inner());
+                            *title = name.clone();
+                            update();
+                        }
+                    },
+                    WindowChange::Close => if let Some(ref name) = e.container.name {
+                        let mut title = title_original.lock().unwrap_or_else(|e| e.into_inner());
+                        if *name == *title {
                             *title = String::from("");
-                            tx.send(Task {
-                                id: id_clone.clone(),
-                                update_time: Instant::now(),
-                            });
+                            update();
                         }
                     },
-                    _ => unreachable!(),
-                }
+                    _ => {}
+                };
             }
+            I3Event::Event(&Event::WorkspaceEvent(ref e)) => {
+                if let WorkspaceChange::Init = e.change {
+                    let mut title = title_original.lock().unwrap_or_else(|e| e.into_inner());
+                    *title = String::from("");
+                    update();
+                }
+            },
+            I3Event::Event(_) => {}
+            I3Event::Connected | I3Event::Disconnected => update(),
         });
 
         Ok(FocusedWindow {
@@ -120,6 +102,11 @@ impl ConfigBlock for FocusedWindow {
 
 impl Block for FocusedWindow {
     fn update(&mut self) -> Result<Option<Duration>> {
+        if !i3_dispatcher::is_connected() {
+            self.text.set_text(DISCONNECTED.to_owned());
+            return Ok(None);
+        }
+
         let mut string = (*self.title
             .lock()
             .block_error("focused_window", "failed to acquire lock")?)
@@ -130,8 +117,8 @@ impl Block for FocusedWindow {
     }
 
     fn view(&self) -> Vec<&I3BarWidget> {
-        let title = &*self.title.lock().unwrap();
-        if String::is_empty(title) {
+        let title = &*self.title.lock().unwrap_or_else(|e| e.into_inner());
+        if String::is_empty(title) && i3_dispatcher::is_connected() {
             vec![]
         } else {
             vec![&self.text]
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use chan::Sender;
+use uuid::Uuid;
+
+use block::{Block, ConfigBlock};
+use config::Config;
+use errors::*;
+use i3_dispatcher::{self, Event, EventKind, I3Event};
+use listener::DISCONNECTED;
+use scheduler::Task;
+use widget::{I3BarWidget, State};
+use widgets::text::TextWidget;
+
+/// The mode i3 is in unless a binding switched to another one.
+const DEFAULT_MODE: &str = "default";
+
+pub struct I3Mode {
+    text: TextWidget,
+    id: String,
+    mode: Arc<Mutex<String>>,
+    modes: HashMap<String, ModeConfig>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ModeConfig {
+    /// Text shown instead of the name of the mode
+    pub text: Option<String>,
+
+    /// State shown in the mode
+    pub state: Option<State>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct I3ModeConfig {
+    /// How to show modes, by name
+    #[serde(default = "I3ModeConfig::default_modes")]
+    pub modes: HashMap<String, ModeConfig>,
+}
+
+impl I3ModeConfig {
+    fn default_modes() -> HashMap<String, ModeConfig> {
+        HashMap::new()
+    }
+}
+
+impl ConfigBlock for I3Mode {
+    type Config = I3ModeConfig;
+
+    fn new(block_config: Self::Config, config: Config, tx: Sender<Task>) -> Result<Self> {
+        let id = Uuid::new_v4().simple().to_string();
+
+        let mode_original = Arc::new(Mutex::new(DEFAULT_MODE.to_owned()));
+        let mode = mode_original.clone();
+
+        let update_id = id.clone();
+        i3_dispatcher::subscribe(&[EventKind::Mode], move |event| {
+            match event {
+                I3Event::Event(&Event::ModeEvent(ref e)) => {
+                    *mode_original.lock().unwrap() = e.change.clone();
+                }
+                // i3 starts in the default mode
+                I3Event::Connected => *mode_original.lock().unwrap() = DEFAULT_MODE.to_owned(),
+                _ => {}
+            }
+            tx.send(Task {
+                id: update_id.clone(),
+                update_time: Instant::now(),
+            })
+        });
+
+        Ok(I3Mode {
+            id,
+            text: TextWidget::new(config),
+            mode,
+            modes: block_config.modes,
+        })
+    }
+}
+
+impl Block for I3Mode {
+    fn update(&mut self) -> Result<Option<Duration>> {
+        if !i3_dispatcher::is_connected() {
+            self.text.set_text(DISCONNECTED.to_owned());
+            self.text.set_state(State::Idle);
+            return Ok(None);
+        }
+
+        let mode = self.mode
+            .lock()
+            .block_error("i3_mode", "failed to acquire lock")?
+            .clone();
+        let (text, state) = match self.modes.get(&mode) {
+            Some(config) => (config.text.clone(), config.state),
+            None => (None, None),
+        };
+        self.text.set_text(text.unwrap_or(mode));
+        self.text.set_state(state.unwrap_or(State::Warning));
+        Ok(None)
+    }
+
+    fn view(&self) -> Vec<&I3BarWidget> {
+        let mode = &*self.mode.lock().unwrap();
+        if mode == DEFAULT_MODE && i3_dispatcher::is_connected() {
+            vec![]
+        } else {
+            vec![&self.text]
+        }
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
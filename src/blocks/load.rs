@@ -1,7 +1,7 @@
 use std::time::Duration;
 
 use block::{Block, ConfigBlock};
-use config::Config;
+use config::{Config, FsRoots};
 use de::deserialize_duration;
 use errors::*;
 use widgets::text::TextWidget;
@@ -22,6 +22,7 @@ pub struct Load {
     format: FormatTemplate,
     id: String,
     update_interval: Duration,
+    roots: FsRoots,
 }
 
 #[derive(Deserialize, Debug, Default, Clone)]
@@ -47,11 +48,12 @@ impl ConfigBlock for Load {
     type Config = LoadConfig;
 
     fn new(block_config: Self::Config, config: Config, _tx_update_request: Sender<Task>) -> Result<Self> {
+        let roots = config.roots.clone();
         let text = TextWidget::new(config)
             .with_icon("cogs")
             .with_state(State::Info);
 
-        let f = File::open("/proc/cpuinfo")
+        let f = File::open(roots.procfs_path("cpuinfo"))
             .block_error("load", "Your system doesn't support /proc/cpuinfo")?;
         let f = BufReader::new(f);
 
@@ -75,6 +77,7 @@ impl ConfigBlock for Load {
             format: FormatTemplate::from_string(&block_config.format)
                 .block_error("load", "Invalid format specified for load")?,
             text,
+            roots,
         })
     }
 }
@@ -83,7 +86,7 @@ impl Block for Load {
     fn update(&mut self) -> Result<Option<Duration>> {
         let mut f = OpenOptions::new()
             .read(true)
-            .open("/proc/loadavg")
+            .open(self.roots.procfs_path("loadavg"))
             .block_error(
                 "load",
                 "Your system does not support reading the load average from /proc/loadavg",
@@ -84,7 +84,7 @@ use std::str::FromStr;
 use uuid::Uuid;
 use std::fmt;
 
-use config::Config;
+use config::{Config, FsRoots};
 use de::deserialize_duration;
 use errors::*;
 use widgets::button::ButtonWidget;
@@ -235,6 +235,7 @@ pub struct Memory {
     values: HashMap<String, String>,
     warning: (f64, f64),
     critical: (f64, f64),
+    roots: FsRoots,
 }
 
 #[derive(Deserialize, Debug, Clone)]
@@ -485,6 +486,7 @@ impl ConfigBlock for Memory {
 
     fn new(block_config: Self::Config, config: Config, tx: Sender<Task>) -> Result<Self> {
         let icons: bool = block_config.icons;
+        let roots = config.roots.clone();
         let widget = ButtonWidget::new(config, "memory").with_text("");
         Ok(Memory {
             id: Uuid::new_v4().simple().to_string(),
@@ -507,6 +509,7 @@ impl ConfigBlock for Memory {
             values: HashMap::<String, String>::new(),
             warning: (block_config.warning_mem, block_config.warning_swap),
             critical: (block_config.critical_mem, block_config.critical_swap),
+            roots,
         })
     }
 }
@@ -518,7 +521,7 @@ impl Block for Memory {
     }
 
     fn update(&mut self) -> Result<Option<Duration>> {
-        let f = File::open("/proc/meminfo")
+        let f = File::open(self.roots.procfs_path("meminfo"))
             .block_error("memory", "/proc/meminfo does not exist")?;
         let f = BufReader::new(f);
 
@@ -13,6 +13,7 @@ mod toggle;
 mod sound;
 mod speedtest;
 mod focused_window;
+mod i3_mode;
 mod xrandr;
 mod net;
 pub mod backlight;
@@ -23,6 +24,8 @@ pub mod maildir;
 mod networkmanager;
 pub mod nightlight;
 pub mod splitstatus;
+mod selfstats;
+mod workspaces;
 
 use config::Config;
 use self::time::*;
@@ -39,6 +42,7 @@ use self::sound::*;
 use self::speedtest::*;
 use self::toggle::*;
 use self::focused_window::*;
+use self::i3_mode::*;
 use self::temperature::*;
 use self::xrandr::*;
 use self::net::*;
@@ -50,6 +54,8 @@ use self::maildir::*;
 use self::networkmanager::*;
 use self::nightlight::*;
 use self::splitstatus::*;
+use self::selfstats::*;
+use self::workspaces::*;
 
 use super::block::{Block, ConfigBlock};
 use errors::*;
@@ -97,6 +103,7 @@ pub fn create_block(name: &str, block_config: Value, config: Config, tx_update_r
             "speedtest" => SpeedTest,
             "temperature" => Temperature,
             "focused_window" => FocusedWindow,
+            "i3_mode" => I3Mode,
             "xrandr" => Xrandr,
             "net" => Net,
             "backlight" => Backlight,
@@ -106,6 +113,127 @@ pub fn create_block(name: &str, block_config: Value, config: Config, tx_update_r
             "maildir" => Maildir,
             "networkmanager" => NetworkManager,
             "nightlight" => NightLight,
-            "splitstatus" => SplitStatus
+            "splitstatus" => SplitStatus,
+            "self" => SelfStats,
+            "workspaces" => Workspaces
     )
 }
+
+/// Icons a block may display whatever its configuration, by block name.
+fn fixed_icons(name: &str) -> &'static [&'static str] {
+    match name {
+        "time" => &["time"],
+        "music" => &[
+            "music",
+            "music_play",
+            "music_pause",
+            "music_next",
+            "music_prev",
+            "music_shuffle",
+            "music_loop",
+            "music_loop_track",
+        ],
+        "load" => &["cogs"],
+        "memory" => &["memory_mem", "memory_swap"],
+        "cpu" => &["cpu"],
+        "pacman" => &["update"],
+        "battery" => &["bat", "bat_full", "bat_charging", "bat_discharging"],
+        "speedtest" => &["ping", "net_down", "net_up"],
+        "temperature" => &["thermometer"],
+        "xrandr" => &["xrandr"],
+        "net" => &["net_up", "net_down", "net_wireless", "net_wired", "net_vpn"],
+        "backlight" => &[
+            "backlight_empty",
+            "backlight_partial1",
+            "backlight_partial2",
+            "backlight_partial3",
+            "backlight_full",
+        ],
+        "weather" => &[
+            "weather_sun",
+            "weather_rain",
+            "weather_clouds",
+            "weather_thunder",
+            "weather_snow",
+            "weather_default",
+        ],
+        "uptime" => &["uptime"],
+        "nvidia_gpu" => &["gpu"],
+        "maildir" => &["mail"],
+        "networkmanager" => &["net_wired", "net_wireless", "net_other"],
+        "splitstatus" => &["horizontal", "vertical"],
+        _ => &[],
+    }
+}
+
+/// Icons a block displays unless configured otherwise, as pairs of the
+/// option choosing the icon and its default.
+fn configurable_icons(name: &str) -> &'static [(&'static str, &'static str)] {
+    match name {
+        "toggle" => &[("icon_on", "toggle_on"), ("icon_off", "toggle_off")],
+        "nightlight" => &[("icon_on", "moon"), ("icon_off", "sun")],
+        _ => &[],
+    }
+}
+
+/// Returns the names of all icons the block `name` may look up with the given configuration.
+pub fn referenced_icons(name: &str, block_config: &Value) -> Vec<String> {
+    let mut icons: Vec<String> = fixed_icons(name).iter().map(|&icon| icon.to_owned()).collect();
+    if let Some(ramp) = block_config.get("icon_ramp").and_then(|ramp| ramp.get("icons")).and_then(Value::as_array) {
+        icons.extend(ramp.iter().filter_map(Value::as_str).map(|icon| icon.to_owned()));
+    }
+    if name == "sound" {
+        // Sources show the microphone icons instead of the volume ones
+        let prefix = match block_config.get("device_kind").and_then(Value::as_str) {
+            Some("source") => "microphone",
+            _ => "volume",
+        };
+        icons.extend(["full", "half", "empty", "muted"].iter().map(|level| format!("{}_{}", prefix, level)));
+        // Icons by device name
+        if let Some(output_icons) = block_config.get("output_icons").and_then(Value::as_table) {
+            icons.extend(output_icons.values().filter_map(Value::as_str).map(|icon| icon.to_owned()));
+        }
+    }
+    for &(option, default) in configurable_icons(name) {
+        icons.push(
+            block_config
+                .get(option)
+                .and_then(Value::as_str)
+                .unwrap_or(default)
+                .to_owned(),
+        );
+    }
+    icons
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use icons;
+
+    #[test]
+    fn built_in_icon_sets_have_all_fixed_icons() {
+        let blocks = [
+            "time", "music", "load", "memory", "cpu", "pacman", "battery", "sound", "speedtest", "temperature",
+            "xrandr", "net", "backlight", "weather", "uptime", "nvidia_gpu", "maildir", "networkmanager",
+            "splitstatus", "toggle", "nightlight",
+        ];
+        let sets = [("none", &*icons::NONE), ("awesome", &*icons::AWESOME), ("material", &*icons::MATERIAL), ("nerd-fonts", &*icons::NERD_FONTS)];
+        let empty = Value::Table(Default::default());
+        for &(set_name, set) in &sets {
+            for block in &blocks {
+                for icon in referenced_icons(block, &empty) {
+                    assert!(set.contains_key(&icon), "{} lacks {} ({})", set_name, icon, block);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn sound_icons_follow_the_device_kind() {
+        let source: Value = ::toml::from_str("device_kind = \"source\"").expect("invalid config");
+        let icons = referenced_icons("sound", &source);
+        assert!(icons.contains(&"microphone_muted".to_owned()));
+        assert!(!icons.iter().any(|icon| icon.starts_with("volume")));
+    }
+}
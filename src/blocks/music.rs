@@ -1,38 +1,52 @@
+use std::collections::HashMap;
 use std::ffi::OsStr;
-use std::process::Command;
+use stats;
 use std::time::{Duration, Instant};
 use chan::Sender;
-use std::thread;
 use std::boxed::Box;
 
 use config::Config;
 use errors::*;
+use dbus_dispatcher::{self, Bus, MatchRule, Message};
+use listener::DISCONNECTED;
 use scheduler::Task;
-use input::I3BarEvent;
+use input::{I3BarEvent, MouseButton};
 use block::{Block, ConfigBlock};
 use de::deserialize_duration;
+use util::FormatTemplate;
 use widgets::rotatingtext::RotatingTextWidget;
 use widgets::button::ButtonWidget;
 use widget::{I3BarWidget, State};
 
-use blocks::dbus::{arg, stdintf, BusType, Connection, ConnectionItem, Message};
-use blocks::dbus::arg::{Array, RefArg};
-use self::stdintf::org_freedesktop_dbus::Properties;
+use blocks::dbus::arg::{self, Array, Dict, RefArg};
 use uuid::Uuid;
 
+/// The prefix of the bus names of MPRIS players.
+const MPRIS_PREFIX: &str = "org.mpris.MediaPlayer2.";
+
 pub struct Music {
     id: String,
     current_song: RotatingTextWidget,
     prev: Option<ButtonWidget>,
     play: Option<ButtonWidget>,
     next: Option<ButtonWidget>,
+    shuffle: Option<ButtonWidget>,
+    repeat: Option<ButtonWidget>,
     on_collapsed_click_widget: ButtonWidget,
     on_collapsed_click: Option<String>,
-    dbus_conn: Connection,
     player_avail: bool,
     marquee: bool,
     player: Option<String>,
-    auto_discover: bool
+    auto_discover: bool,
+    /// Whether the player was picked by scrolling, rather than for playing.
+    pinned: bool,
+    format: FormatTemplate,
+    /// Whether the format shows how far the track has been played, which is
+    /// then polled while playing.
+    shows_position: bool,
+    progress_width: usize,
+    seek_step: Duration,
+    volume_step: f64,
 }
 
 #[derive(Deserialize, Debug, Default, Clone)]
@@ -58,12 +72,28 @@ pub struct MusicConfig {
     #[serde(default = "MusicConfig::default_marquee_speed", deserialize_with = "deserialize_duration")]
     pub marquee_speed: Duration,
 
-    /// Array of control buttons to be displayed. Options are<br/>prev (previous title), play (play/pause) and next (next title)
+    /// Array of control buttons to be displayed. Options are<br/>prev (previous title), play (play/pause), next (next title),<br/>shuffle and loop
     #[serde(default = "MusicConfig::default_buttons")]
     pub buttons: Vec<String>,
 
     #[serde(default = "MusicConfig::default_on_collapsed_click")]
     pub on_collapsed_click: Option<String>,
+
+    /// Format override
+    #[serde(default = "MusicConfig::default_format")]
+    pub format: String,
+
+    /// Width of the progress bar in characters
+    #[serde(default = "MusicConfig::default_progress_width")]
+    pub progress_width: usize,
+
+    /// How far scrolling seeks, in seconds
+    #[serde(default = "MusicConfig::default_seek_step", deserialize_with = "deserialize_duration")]
+    pub seek_step: Duration,
+
+    /// How much shift and scrolling changes the volume, in percent
+    #[serde(default = "MusicConfig::default_volume_step")]
+    pub volume_step: f64,
 }
 
 impl MusicConfig {
@@ -90,6 +120,22 @@ impl MusicConfig {
     fn default_on_collapsed_click() -> Option<String> {
         None
     }
+
+    fn default_format() -> String {
+        "{title} | {artist}".to_owned()
+    }
+
+    fn default_progress_width() -> usize {
+        10
+    }
+
+    fn default_seek_step() -> Duration {
+        Duration::from_secs(5)
+    }
+
+    fn default_volume_step() -> f64 {
+        5f64
+    }
 }
 
 impl ConfigBlock for Music {
@@ -99,49 +145,41 @@ impl ConfigBlock for Music {
         let id: String = Uuid::new_v4().simple().to_string();
         let id_copy = id.clone();
 
-        thread::spawn(move || {
-            let c = Connection::get_private(BusType::Session).unwrap();
-            c.add_match(
-                "interface='org.freedesktop.DBus.Properties',member='PropertiesChanged',path='/org/mpris/MediaPlayer2'",
-            ).unwrap();
-            loop {
-                for ci in c.iter(100_000) {
-                    if let ConnectionItem::Signal(_) = ci {
-                        send.send(Task {
-                            id: id.clone(),
-                            update_time: Instant::now(),
-                        });
-                    }
-                }
-            }
-        });
+        dbus_dispatcher::subscribe(
+            Bus::Session,
+            MatchRule::new()
+                .with_interface("org.freedesktop.DBus.Properties")
+                .with_member("PropertiesChanged")
+                .with_path("/org/mpris/MediaPlayer2"),
+            move |_| {
+                send.send(Task {
+                    id: id.clone(),
+                    update_time: Instant::now(),
+                })
+            },
+        )?;
+
+        // Buttons are named after the block, so that several music blocks
+        // don't react to each other's clicks
+        let prefix = id_copy.clone();
+        let button = |name: &str, icon: &str| {
+            ButtonWidget::new(config.clone(), &format!("{}-{}", prefix, name))
+                .with_icon(icon)
+                .with_state(State::Info)
+        };
 
         let mut play: Option<ButtonWidget> = None;
         let mut prev: Option<ButtonWidget> = None;
         let mut next: Option<ButtonWidget> = None;
-        for button in block_config.buttons {
-            match &*button {
-                "play" => {
-                    play = Some(
-                        ButtonWidget::new(config.clone(), "play")
-                            .with_icon("music_play")
-                            .with_state(State::Info),
-                    )
-                }
-                "next" => {
-                    next = Some(
-                        ButtonWidget::new(config.clone(), "next")
-                            .with_icon("music_next")
-                            .with_state(State::Info),
-                    )
-                }
-                "prev" => {
-                    prev = Some(
-                        ButtonWidget::new(config.clone(), "prev")
-                            .with_icon("music_prev")
-                            .with_state(State::Info),
-                    )
-                }
+        let mut shuffle: Option<ButtonWidget> = None;
+        let mut repeat: Option<ButtonWidget> = None;
+        for name in block_config.buttons {
+            match &*name {
+                "play" => play = Some(button("play", "music_play")),
+                "next" => next = Some(button("next", "music_next")),
+                "prev" => prev = Some(button("prev", "music_prev")),
+                "shuffle" => shuffle = Some(button("shuffle", "music_shuffle")),
+                "loop" => repeat = Some(button("loop", "music_loop")),
                 x => Err(BlockError(
                     "music".to_owned(),
                     format!("unknown music button identifier: '{}'", x),
@@ -150,70 +188,183 @@ impl ConfigBlock for Music {
         }
 
         Ok(Music {
-            id: id_copy,
             current_song: RotatingTextWidget::new(
                 Duration::new(block_config.marquee_interval.as_secs(), 0),
                 Duration::new(0, block_config.marquee_speed.subsec_nanos()),
                 block_config.max_width,
                 config.clone(),
             ).with_icon("music")
-                .with_state(State::Info),
+                .with_state(State::Info)
+                .with_name(&id_copy),
             prev,
             play,
             next,
-            on_collapsed_click_widget: ButtonWidget::new(
-                config.clone(),
-                "on_collapsed_click",
-            ).with_icon("music")
-                .with_state(State::Info),
+            shuffle,
+            repeat,
+            on_collapsed_click_widget: button("on_collapsed_click", "music"),
             on_collapsed_click: block_config.on_collapsed_click,
-            dbus_conn: Connection::get_private(BusType::Session)
-                .block_error("music", "failed to establish D-Bus connection")?,
             player_avail: false,
             auto_discover: block_config.player.is_none(),
-            player: if block_config.player.is_none()
-                {
-                    block_config.player
-                } else {
-                    Some(format!("org.mpris.MediaPlayer2.{}", block_config.player.unwrap()))
-                },
+            player: block_config.player.map(|player| format!("{}{}", MPRIS_PREFIX, player)),
+            pinned: false,
             marquee: block_config.marquee,
+            format: FormatTemplate::from_string(&block_config.format)
+                .block_error("music", "invalid format specified for music")?,
+            shows_position: block_config.format.contains("{position}") || block_config.format.contains("{progress}"),
+            progress_width: block_config.progress_width,
+            seek_step: block_config.seek_step,
+            volume_step: block_config.volume_step,
+            id: id_copy,
         })
     }
 }
 
+impl Music {
+    /// Picks the player to show among all running ones: the one chosen by
+    /// scrolling while it runs, else one that is playing (the current one
+    /// first), else the current one, else any.
+    fn select_player(&mut self) {
+        let players = list_players();
+        let current = match self.player.take() {
+            Some(current) => if players.contains(&current) { Some(current) } else { None },
+            None => None,
+        };
+        let is_playing = |player: &String| playback_status(player).map(|status| status == "Playing").unwrap_or(false);
+        if current.as_ref().map_or(false, |current| self.pinned || is_playing(current)) {
+            self.player = current;
+            return;
+        }
+
+        self.pinned = false;
+        self.player = players
+            .iter()
+            .find(|player| is_playing(player))
+            .cloned()
+            .or(current)
+            .or_else(|| players.first().cloned());
+    }
+
+    /// Switches to the next (or previous) running player.
+    fn cycle_player(&mut self, forward: bool) {
+        let players = list_players();
+        if players.is_empty() {
+            return;
+        }
+
+        let index = match self.player.as_ref().and_then(|current| players.iter().position(|player| player == current)) {
+            Some(index) if forward => (index + 1) % players.len(),
+            Some(index) => (index + players.len() - 1) % players.len(),
+            None => 0,
+        };
+        self.player = Some(players[index].clone());
+        self.pinned = true;
+    }
+
+    /// Seeks `steps` steps forward, or backward if negative.
+    fn seek(&self, player: &str, steps: i64) -> Result<()> {
+        let step = self.seek_step.as_secs() as i64 * 1_000_000 + i64::from(self.seek_step.subsec_nanos()) / 1000;
+        let m = Message::new_method_call(player, "/org/mpris/MediaPlayer2", "org.mpris.MediaPlayer2.Player", "Seek")
+            .block_error("music", "failed to create D-Bus method call")?
+            .append1(steps * step);
+        dbus_dispatcher::send(Bus::Session, m)
+    }
+
+    /// Changes the volume of the player by `steps` steps.
+    fn change_volume(&self, player: &str, steps: i64) -> Result<()> {
+        let volume = get_player_property(player, "Volume")?
+            .as_f64()
+            .block_error("music", "the player has no volume")?;
+        let volume = (volume + steps as f64 * self.volume_step / 100f64).max(0f64).min(1f64);
+        set_player_property(player, "Volume", volume)
+    }
+}
+
 impl Block for Music {
     fn id(&self) -> &str {
         &self.id
     }
 
     fn update(&mut self) -> Result<Option<Duration>> {
+        if !dbus_dispatcher::is_connected(Bus::Session) {
+            self.player_avail = false;
+            self.current_song.set_text(DISCONNECTED.to_owned());
+            self.current_song.set_state(State::Idle);
+            return Ok(Some(Duration::new(2, 0)));
+        }
+        self.current_song.set_state(State::Info);
+
         let (rotated, next) = if self.marquee {
             self.current_song.next()?
         } else {
             (false, None)
         };
-        if !rotated && self.player.is_none() {
-            self.player = get_first_available_player(&self.dbus_conn)
+        if !rotated && self.auto_discover {
+            self.select_player();
         }
+        // Set while the position has to be polled
+        let mut poll = None;
         if !(rotated || self.player.is_none()) {
-            let c = self.dbus_conn.with_path(
-                self.player.clone().unwrap(),
-                "/org/mpris/MediaPlayer2",
-                1000,
-            );
-            let data = c.get("org.mpris.MediaPlayer2.Player", "Metadata");
+            let player = self.player.clone().unwrap();
 
-            if let Ok(metadata) = data {
-                let (title, artist) = extract_from_metadata(&metadata).unwrap_or((String::new(), String::new()));
+            if let Ok(properties) = get_player_properties(&player) {
+                let metadata = properties
+                    .get("Metadata")
+                    .and_then(|metadata| extract_from_metadata(metadata).ok())
+                    .unwrap_or_default();
+                let playing = properties.string("PlaybackStatus").map_or(false, |status| status == "Playing");
 
-                if title.is_empty() && artist.is_empty() {
+                if metadata.title.is_empty() && metadata.artist.is_empty() {
                     self.player_avail = false;
                     self.current_song.set_text(String::new());
                 } else {
                     self.player_avail = true;
-                    self.current_song
-                        .set_text(format!("{} | {}", title, artist));
+                    let position = if self.shows_position {
+                        properties.integer("Position")
+                    } else {
+                        None
+                    };
+                    if self.shows_position && playing {
+                        poll = Some(Duration::from_secs(1));
+                    }
+                    let progress = match (position, metadata.length) {
+                        (Some(position), Some(length)) => progress_bar(position, length, self.progress_width),
+                        _ => String::new(),
+                    };
+                    let volume = properties.get("Volume").and_then(|volume| volume.as_f64());
+                    let values = map!("{title}" => metadata.title,
+                                      "{artist}" => metadata.artist,
+                                      "{album}" => metadata.album,
+                                      "{track}" => metadata.track.map(|track| track.to_string()).unwrap_or_default(),
+                                      "{length}" => metadata.length.map(format_time).unwrap_or_default(),
+                                      "{position}" => position.map(format_time).unwrap_or_default(),
+                                      "{progress}" => progress,
+                                      "{volume}" => volume.map(|volume| format!("{:.0}", volume * 100f64)).unwrap_or_default(),
+                                      "{player}" => player_name(&player).to_owned());
+                    self.current_song.set_text(self.format.render_static_str(&values)?);
+                }
+
+                if let Some(ref mut play) = self.play {
+                    play.set_icon(if playing { "music_pause" } else { "music_play" });
+                }
+                if let Some(ref mut shuffle) = self.shuffle {
+                    let on = properties.integer("Shuffle").map_or(false, |shuffle| shuffle != 0);
+                    shuffle.set_state(if on { State::Info } else { State::Idle });
+                }
+                if let Some(ref mut repeat) = self.repeat {
+                    match properties.string("LoopStatus").as_ref().map(|status| status.as_str()) {
+                        Some("Track") => {
+                            repeat.set_icon("music_loop_track");
+                            repeat.set_state(State::Info);
+                        }
+                        Some("Playlist") => {
+                            repeat.set_icon("music_loop");
+                            repeat.set_state(State::Info);
+                        }
+                        _ => {
+                            repeat.set_icon("music_loop");
+                            repeat.set_state(State::Idle);
+                        }
+                    }
                 }
             } else {
                 self.current_song.set_text(String::from(""));
@@ -222,62 +373,92 @@ impl Block for Music {
                     self.player = None;
                 }
             }
-
-            if let Some(ref mut play) = self.play {
-                let data = c.get("org.mpris.MediaPlayer2.Player", "PlaybackStatus");
-                match data {
-                    Err(_) => play.set_icon("music_play"),
-                    Ok(data) => {
-                        let data: Box<RefArg> = data;
-                        let state = data;
-                        if state.as_str().map(|s| s != "Playing").unwrap_or(false) {
-                            play.set_icon("music_play")
-                        } else {
-                            play.set_icon("music_pause")
-                        }
-                    }
-                }
-            }
         }
-        Ok(match (next, self.marquee) {
-            (Some(_), _) => next,
-            (None, _) => Some(Duration::new(2, 0))
+        Ok(match (next, poll) {
+            (Some(next), Some(poll)) => Some(next.min(poll)),
+            (Some(next), None) => Some(next),
+            (None, poll) => Some(poll.unwrap_or_else(|| Duration::new(2, 0))),
         })
     }
 
     fn click(&mut self, event: &I3BarEvent) -> Result<()> {
-        if let Some(ref name) = event.name {
-            let action = match name as &str {
-                "play" => "PlayPause",
-                "next" => "Next",
-                "prev" => "Previous",
-                _ => "",
+        let name = match event.name {
+            Some(ref name) => name,
+            None => return Ok(()),
+        };
+
+        if *name == self.id {
+            let steps = match event.button {
+                MouseButton::WheelUp => 1,
+                MouseButton::WheelDown => -1,
+                _ => return Ok(()),
             };
-            if action != "" {
-                let m = Message::new_method_call(
-                    self.player.as_ref().unwrap(),
-                    "/org/mpris/MediaPlayer2",
-                    "org.mpris.MediaPlayer2.Player",
-                    action,
-                ).block_error("music", "failed to create D-Bus method call")?;
-                self.dbus_conn
-                    .send(m)
-                    .block_error("music", "failed to call method via D-Bus")
-                    .map(|_| ())
-            } else {
-                if name == "on_collapsed_click" && self.on_collapsed_click.is_some() {
-                    let command = self.on_collapsed_click.clone().unwrap();
-                    let command_broken: Vec<&str> = command.split_whitespace().collect();
-                    let mut itr = command_broken.iter();
-                    let mut _cmd = Command::new(OsStr::new(&itr.next().unwrap()))
-                        .args(itr)
-                        .spawn();
+            if event.has_modifier("Control") {
+                if !self.auto_discover {
+                    return Ok(());
+                }
+                self.cycle_player(steps < 0);
+            } else if let Some(player) = self.player.clone() {
+                if event.has_modifier("Shift") {
+                    self.change_volume(&player, steps)?;
+                } else {
+                    self.seek(&player, steps)?;
                 }
-                Ok(())
             }
-        } else {
-            Ok(())
+            return self.update().map(|_| ());
+        }
+
+        let prefix = format!("{}-", self.id);
+        if !name.starts_with(&prefix) {
+            return Ok(());
+        }
+        let button = &name[prefix.len()..];
+        let action = match button {
+            "play" => "PlayPause",
+            "next" => "Next",
+            "prev" => "Previous",
+            _ => "",
+        };
+        if action != "" {
+            let player = match self.player {
+                Some(ref player) => player,
+                None => return Ok(()),
+            };
+            let m = Message::new_method_call(
+                player,
+                "/org/mpris/MediaPlayer2",
+                "org.mpris.MediaPlayer2.Player",
+                action,
+            ).block_error("music", "failed to create D-Bus method call")?;
+            dbus_dispatcher::send(Bus::Session, m)?;
+        } else if button == "shuffle" || button == "loop" {
+            let player = match self.player {
+                Some(ref player) => player,
+                None => return Ok(()),
+            };
+            if button == "shuffle" {
+                let on = get_player_property(player, "Shuffle")
+                    .ok()
+                    .and_then(|shuffle| integer(&*shuffle))
+                    .map_or(false, |shuffle| shuffle != 0);
+                set_player_property(player, "Shuffle", !on)?;
+            } else {
+                let next = match loop_status(player).as_ref().map(|status| status.as_str()) {
+                    Some("Playlist") => "Track",
+                    Some("Track") => "None",
+                    _ => "Playlist",
+                };
+                set_player_property(player, "LoopStatus", next)?;
+            }
+        } else if button == "on_collapsed_click" && self.on_collapsed_click.is_some() {
+            let command = self.on_collapsed_click.clone().unwrap();
+            let command_broken: Vec<&str> = command.split_whitespace().collect();
+            let mut itr = command_broken.iter();
+            let mut _cmd = stats::command(OsStr::new(&itr.next().unwrap()))
+                .args(itr)
+                .spawn();
         }
+        Ok(())
     }
 
     fn view(&self) -> Vec<&I3BarWidget> {
@@ -293,6 +474,12 @@ impl Block for Music {
             if let Some(ref next) = self.next {
                 elements.push(next);;
             }
+            if let Some(ref shuffle) = self.shuffle {
+                elements.push(shuffle);
+            }
+            if let Some(ref repeat) = self.repeat {
+                elements.push(repeat);
+            }
             elements
         } else {
             if self.current_song.is_empty() {
@@ -304,9 +491,19 @@ impl Block for Music {
     }
 }
 
-fn extract_from_metadata(metadata: &Box<arg::RefArg>) -> Result<(String, String)> {
-    let mut title = String::new();
-    let mut artist = String::new();
+/// What the block shows of the metadata of a track.
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Metadata {
+    title: String,
+    artist: String,
+    album: String,
+    track: Option<i64>,
+    /// Length of the track in microseconds
+    length: Option<i64>,
+}
+
+fn extract_from_metadata(metadata: &Box<arg::RefArg>) -> Result<Metadata> {
+    let mut extracted = Metadata::default();
 
     let mut iter = metadata
         .as_iter()
@@ -319,7 +516,7 @@ fn extract_from_metadata(metadata: &Box<arg::RefArg>) -> Result<(String, String)
             .block_error("music", "failed to extract metadata")?
         {
             "xesam:artist" => {
-                artist = String::from(value
+                extracted.artist = String::from(value
                     .as_iter()
                     .block_error("music", "failed to extract metadata")?
                     .nth(0)
@@ -336,24 +533,393 @@ fn extract_from_metadata(metadata: &Box<arg::RefArg>) -> Result<(String, String)
                     .block_error("music", "failed to extract metadata")?)
             }
             "xesam:title" => {
-                title = String::from(value
+                extracted.title = String::from(value
                     .as_str()
                     .block_error("music", "failed to extract metadata")?)
             }
+            "xesam:album" => {
+                extracted.album = String::from(value
+                    .as_str()
+                    .block_error("music", "failed to extract metadata")?)
+            }
+            "xesam:trackNumber" => extracted.track = integer(value),
+            "mpris:length" => extracted.length = integer(value),
             _ => {}
         };
     }
-    Ok((title, artist))
+    Ok(extracted)
+}
+
+/// Reads an integer, whichever its D-Bus type: players disagree on the
+/// signedness of lengths.
+fn integer(value: &RefArg) -> Option<i64> {
+    value.as_i64().or_else(|| value.as_u64().map(|value| value as i64))
 }
 
-fn get_first_available_player(connection: &Connection) -> Option<String> {
-    let m = Message::new_method_call("org.freedesktop.DBus", "/", "org.freedesktop.DBus", "ListNames").unwrap();
-    let r = connection.send_with_reply_and_block(m, 2000).unwrap();
-    // ListNames returns one argument, which is an array of strings.
-    let mut arr: Array<&str, _>  = r.get1().unwrap();
-    if let Some(name) = arr.find(|entry| entry.starts_with("org.mpris.MediaPlayer2")) {
-        Some(String::from(name))
+/// Formats a time in microseconds like `3:07`, or `1:03:07` past an hour.
+fn format_time(micros: i64) -> String {
+    let seconds = micros.max(0) / 1_000_000;
+    if seconds >= 3600 {
+        format!("{}:{:02}:{:02}", seconds / 3600, seconds % 3600 / 60, seconds % 60)
     } else {
-        None
+        format!("{}:{:02}", seconds / 60, seconds % 60)
+    }
+}
+
+/// A bar of `width` characters, filled as far as `position` is into `length`.
+fn progress_bar(position: i64, length: i64, width: usize) -> String {
+    let filled = if length > 0 {
+        ((position.max(0) as f64 / length as f64).min(1f64) * width as f64).round() as usize
+    } else {
+        0
+    };
+    format!("{}{}", "\u{2588}".repeat(filled), "\u{2591}".repeat(width - filled))
+}
+
+/// The name of the player with the bus name `player`, like `spotify`.
+fn player_name(player: &str) -> &str {
+    // Players running several instances append `.instance<pid>`
+    player
+        .trim_left_matches(MPRIS_PREFIX)
+        .split('.')
+        .next()
+        .unwrap_or(player)
+}
+
+/// All properties of the player interface of an MPRIS player, by name.
+struct PlayerProperties(HashMap<String, Box<RefArg>>);
+
+impl PlayerProperties {
+    fn get(&self, property: &str) -> Option<&Box<RefArg>> {
+        self.0.get(property)
+    }
+
+    fn string(&self, property: &str) -> Option<String> {
+        self.get(property).and_then(|value| value.as_str()).map(String::from)
+    }
+
+    fn integer(&self, property: &str) -> Option<i64> {
+        self.get(property).and_then(|value| integer(&**value))
+    }
+}
+
+/// Reads all properties of the MPRIS player with the bus name `player` in
+/// one call.
+fn get_player_properties(player: &str) -> Result<PlayerProperties> {
+    let m = Message::new_method_call(player, "/org/mpris/MediaPlayer2", "org.freedesktop.DBus.Properties", "GetAll")
+        .block_error("music", "failed to create D-Bus method call")?
+        .append1("org.mpris.MediaPlayer2.Player");
+    let reply = dbus_dispatcher::call(Bus::Session, m)?;
+    let properties: Dict<&str, arg::Variant<Box<RefArg>>, _> = reply
+        .get1()
+        .block_error("music", "failed to read player properties")?;
+    Ok(PlayerProperties(
+        properties.map(|(name, value)| (name.to_owned(), value.0)).collect(),
+    ))
+}
+
+/// Reads `property` of the MPRIS player with the bus name `player`.
+fn get_player_property(player: &str, property: &str) -> Result<Box<RefArg>> {
+    let reply = dbus_dispatcher::get_property(
+        Bus::Session,
+        player,
+        "/org/mpris/MediaPlayer2",
+        "org.mpris.MediaPlayer2.Player",
+        property,
+    )?;
+    let value: arg::Variant<Box<RefArg>> = reply
+        .get1()
+        .block_error("music", "failed to read player property")?;
+    Ok(value.0)
+}
+
+/// Sets `property` of the MPRIS player with the bus name `player`.
+fn set_player_property<T: arg::Arg + arg::Append>(player: &str, property: &str, value: T) -> Result<()> {
+    let m = Message::new_method_call(player, "/org/mpris/MediaPlayer2", "org.freedesktop.DBus.Properties", "Set")
+        .block_error("music", "failed to create D-Bus method call")?
+        .append3("org.mpris.MediaPlayer2.Player", property, arg::Variant(value));
+    dbus_dispatcher::send(Bus::Session, m)
+}
+
+/// `None`, `Track` or `Playlist`, if the player supports looping.
+fn loop_status(player: &str) -> Option<String> {
+    get_player_property(player, "LoopStatus")
+        .ok()
+        .and_then(|status| status.as_str().map(String::from))
+}
+
+/// `Playing`, `Paused` or `Stopped`, if the player tells.
+fn playback_status(player: &str) -> Option<String> {
+    get_player_property(player, "PlaybackStatus")
+        .ok()
+        .and_then(|status| status.as_str().map(String::from))
+}
+
+/// The bus names of all running MPRIS players.
+fn list_players() -> Vec<String> {
+    let names = Message::new_method_call("org.freedesktop.DBus", "/", "org.freedesktop.DBus", "ListNames")
+        .ok()
+        .and_then(|m| dbus_dispatcher::call(Bus::Session, m).ok());
+    match names {
+        Some(reply) => {
+            // ListNames returns one argument, which is an array of strings.
+            let names: Option<Array<&str, _>> = reply.get1();
+            names
+                .map(|names| names.filter(|name| name.starts_with(MPRIS_PREFIX)).map(String::from).collect())
+                .unwrap_or_default()
+        }
+        None => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use blocks::dbus::{Connection, ConnectionItem, NameFlag};
+    use std::env;
+    use std::io::{BufRead, BufReader};
+    use std::process::{Child, Command, Stdio};
+    use std::sync::mpsc;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    #[test]
+    fn music_times_show_minutes_and_hours() {
+        assert_eq!(format_time(0), "0:00");
+        assert_eq!(format_time(187_000_000), "3:07");
+        assert_eq!(format_time(3_787_000_000), "1:03:07");
+        assert_eq!(format_time(-5_000_000), "0:00");
+    }
+
+    #[test]
+    fn music_progress_bar_fills_with_the_position() {
+        assert_eq!(progress_bar(0, 100, 4), "\u{2591}\u{2591}\u{2591}\u{2591}");
+        assert_eq!(progress_bar(50, 100, 4), "\u{2588}\u{2588}\u{2591}\u{2591}");
+        assert_eq!(progress_bar(150, 100, 4), "\u{2588}\u{2588}\u{2588}\u{2588}");
+        assert_eq!(progress_bar(-10, 100, 4), "\u{2591}\u{2591}\u{2591}\u{2591}");
+        assert_eq!(progress_bar(50, 0, 2), "\u{2591}\u{2591}");
+    }
+
+    /// A private session bus, stopped when dropped.
+    struct PrivateBus {
+        daemon: Child,
+        address: String,
+    }
+
+    impl PrivateBus {
+        fn start() -> Option<PrivateBus> {
+            let mut daemon = Command::new("dbus-daemon")
+                .args(&["--session", "--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .spawn()
+                .ok()?;
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?).read_line(&mut address).ok()?;
+            Some(PrivateBus {
+                daemon,
+                address: address.trim().to_owned(),
+            })
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+
+    /// The state of the fake player, and the calls it answered.
+    struct FakePlayer {
+        playing: bool,
+        shuffle: bool,
+        loop_status: String,
+        volume: f64,
+        calls: Vec<String>,
+    }
+
+    impl FakePlayer {
+        fn new() -> Self {
+            FakePlayer {
+                playing: true,
+                shuffle: false,
+                loop_status: "Track".to_owned(),
+                volume: 0.5,
+                calls: Vec::new(),
+            }
+        }
+
+        fn property(&self, name: &str) -> Option<Box<RefArg>> {
+            let value: Box<RefArg> = match name {
+                "Metadata" => {
+                    let mut metadata: HashMap<String, arg::Variant<Box<RefArg>>> = HashMap::new();
+                    metadata.insert("xesam:title".to_owned(), arg::Variant(Box::new("Song".to_owned())));
+                    metadata.insert("xesam:artist".to_owned(), arg::Variant(Box::new(vec!["Band".to_owned()])));
+                    metadata.insert("mpris:length".to_owned(), arg::Variant(Box::new(180_000_000i64)));
+                    Box::new(metadata)
+                }
+                "PlaybackStatus" => Box::new(if self.playing { "Playing" } else { "Paused" }.to_owned()),
+                "Shuffle" => Box::new(self.shuffle),
+                "LoopStatus" => Box::new(self.loop_status.clone()),
+                "Position" => Box::new(60_000_000i64),
+                "Volume" => Box::new(self.volume),
+                _ => return None,
+            };
+            Some(value)
+        }
+
+        /// Answers `call`, recording it with its arguments.
+        fn answer(&mut self, call: &Message) -> Message {
+            let member = call.member().map(|member| (&*member).to_owned()).unwrap_or_default();
+            let reply = Message::new_method_return(call).expect("failed to create reply");
+            match &*member {
+                "GetAll" => {
+                    self.calls.push("GetAll".to_owned());
+                    let mut properties: HashMap<&str, arg::Variant<Box<RefArg>>> = HashMap::new();
+                    for name in &["Metadata", "PlaybackStatus", "Shuffle", "LoopStatus", "Position", "Volume"] {
+                        properties.insert(*name, arg::Variant(self.property(name).unwrap()));
+                    }
+                    reply.append1(properties)
+                }
+                "Get" => {
+                    let (_, name) = call.get2::<&str, &str>();
+                    let name = name.expect("Get without a property");
+                    self.calls.push(format!("Get {}", name));
+                    reply.append1(arg::Variant(self.property(name).expect("unknown property")))
+                }
+                "Set" => {
+                    let (_, name, value) = call.get3::<&str, &str, arg::Variant<Box<RefArg>>>();
+                    let (name, value) = (name.expect("Set without a property"), value.expect("Set without a value").0);
+                    let shown = match name {
+                        "Shuffle" => {
+                            self.shuffle = integer(&*value) == Some(1);
+                            self.shuffle.to_string()
+                        }
+                        "LoopStatus" => {
+                            self.loop_status = value.as_str().expect("LoopStatus is a string").to_owned();
+                            self.loop_status.clone()
+                        }
+                        "Volume" => {
+                            self.volume = value.as_f64().expect("Volume is a number");
+                            format!("{:.2}", self.volume)
+                        }
+                        _ => panic!("unexpected property {}", name),
+                    };
+                    self.calls.push(format!("Set {} {}", name, shown));
+                    reply
+                }
+                "Seek" => {
+                    self.calls.push(format!("Seek {}", call.get1::<i64>().expect("Seek without an offset")));
+                    reply
+                }
+                _ => {
+                    self.calls.push(member);
+                    reply
+                }
+            }
+        }
+    }
+
+    /// Serves the player interface of the MPRIS player `test` on the bus at
+    /// `address`. Returns once the player is on the bus.
+    fn serve_fake_player(address: &str, player: Arc<Mutex<FakePlayer>>) {
+        let address = address.to_owned();
+        let (tx_ready, rx_ready) = mpsc::channel();
+        thread::spawn(move || {
+            let c = Connection::open_private(&address).expect("failed to connect to the private bus");
+            c.register().expect("failed to register on the private bus");
+            c.register_name("org.mpris.MediaPlayer2.test", NameFlag::ReplaceExisting as u32)
+                .expect("failed to own the player name");
+            c.register_object_path("/org/mpris/MediaPlayer2")
+                .expect("failed to register the player object");
+            tx_ready.send(()).expect("the test stopped");
+
+            for item in c.iter(100) {
+                if let ConnectionItem::MethodCall(call) = item {
+                    let reply = player.lock().unwrap().answer(&call);
+                    c.send(reply).expect("failed to reply");
+                }
+            }
+        });
+        rx_ready.recv().expect("the fake player failed to start");
+    }
+
+    fn click_event(name: &str, button: MouseButton, modifiers: &[&str]) -> I3BarEvent {
+        I3BarEvent {
+            name: Some(name.to_owned()),
+            instance: None,
+            x: 0,
+            y: 0,
+            button,
+            modifiers: modifiers.iter().map(|modifier| modifier.to_string()).collect(),
+        }
+    }
+
+    /// Takes the calls the fake player answered since the last time.
+    fn take_calls(player: &Arc<Mutex<FakePlayer>>) -> Vec<String> {
+        ::std::mem::replace(&mut player.lock().unwrap().calls, Vec::new())
+    }
+
+    /// The D-Bus dispatcher connects to the session bus once per process, so
+    /// this is the only test using it. It needs `dbus-daemon`, and runs with
+    /// `cargo test -- --ignored`.
+    #[test]
+    #[ignore]
+    fn music_controls_a_player_on_the_session_bus() {
+        let bus = PrivateBus::start().expect("failed to start dbus-daemon");
+        env::set_var("DBUS_SESSION_BUS_ADDRESS", &bus.address);
+        let player = Arc::new(Mutex::new(FakePlayer::new()));
+        serve_fake_player(&bus.address, player.clone());
+
+        // All properties are read in one call
+        let properties = get_player_properties("org.mpris.MediaPlayer2.test").expect("failed to read properties");
+        let metadata = extract_from_metadata(properties.get("Metadata").expect("no metadata")).expect("invalid metadata");
+        assert_eq!(metadata.title, "Song");
+        assert_eq!(metadata.artist, "Band");
+        assert_eq!(metadata.length, Some(180_000_000));
+        assert_eq!(properties.string("PlaybackStatus"), Some("Playing".to_owned()));
+        assert_eq!(properties.string("LoopStatus"), Some("Track".to_owned()));
+        assert_eq!(properties.integer("Position"), Some(60_000_000));
+        assert_eq!(properties.get("Volume").and_then(|volume| volume.as_f64()), Some(0.5));
+        assert_eq!(take_calls(&player), vec!["GetAll"]);
+
+        let block_config: MusicConfig = ::toml::from_str(
+            "player = \"test\"\nmarquee = false\nformat = \"{title} {position}\"\nbuttons = [\"shuffle\", \"loop\"]",
+        ).expect("invalid music configuration");
+        let (tx, _rx) = ::chan::async();
+        let mut music = Music::new(block_config, Config::default(), tx).expect("failed to create the music block");
+        let id = music.id().to_owned();
+
+        // The position is polled every second while playing only
+        assert_eq!(music.update().unwrap(), Some(Duration::from_secs(1)));
+        player.lock().unwrap().playing = false;
+        assert_eq!(music.update().unwrap(), Some(Duration::from_secs(2)));
+        take_calls(&player);
+
+        // Each click updates the block, whose GetAll call is answered after
+        // the messages sent before it
+        music.click(&click_event(&id, MouseButton::WheelUp, &[])).unwrap();
+        music.click(&click_event(&id, MouseButton::WheelDown, &[])).unwrap();
+        assert_eq!(take_calls(&player), vec!["Seek 5000000", "GetAll", "Seek -5000000", "GetAll"]);
+
+        music.click(&click_event(&id, MouseButton::WheelUp, &["Shift"])).unwrap();
+        assert_eq!(take_calls(&player), vec!["Get Volume", "Set Volume 0.55", "GetAll"]);
+
+        music.click(&click_event(&format!("{}-shuffle", id), MouseButton::Left, &[])).unwrap();
+        music.click(&click_event(&format!("{}-loop", id), MouseButton::Left, &[])).unwrap();
+        music.click(&click_event(&format!("{}-loop", id), MouseButton::Left, &[])).unwrap();
+        music.update().unwrap();
+        assert_eq!(
+            take_calls(&player),
+            vec![
+                "Get Shuffle",
+                "Set Shuffle true",
+                "Get LoopStatus",
+                "Set LoopStatus None",
+                "Get LoopStatus",
+                "Set LoopStatus Playlist",
+                "GetAll",
+            ]
+        );
     }
 }
@@ -1,12 +1,12 @@
 use std::fs::OpenOptions;
 use std::io::prelude::*;
 use std::path::{Path, PathBuf};
-use std::process::Command;
+use stats;
 use std::time::{Duration, Instant};
 use chan::Sender;
 
 use block::{Block, ConfigBlock};
-use config::Config;
+use config::{Config, FsRoots};
 use de::deserialize_duration;
 use errors::*;
 use widgets::text::TextWidget;
@@ -26,8 +26,8 @@ pub struct NetworkDevice {
 impl NetworkDevice {
     /// Use the network device `device`. Raises an error if a directory for that
     /// device is not found.
-    pub fn from_device(device: String) -> Result<Self> {
-        let device_path = Path::new("/sys/class/net").join(device.clone());
+    pub fn from_device(device: String, roots: &FsRoots) -> Result<Self> {
+        let device_path = roots.sysfs_path("class/net").join(device.clone());
         if !device_path.exists() {
             return Err(BlockError(
                 "net".to_string(),
@@ -101,7 +101,7 @@ impl NetworkDevice {
                     .to_string(),
             ));
         }
-        let mut iw_output = Command::new("sh")
+        let mut iw_output = stats::command("sh")
             .args(&[
                 "-c",
                 &format!(
@@ -114,7 +114,7 @@ impl NetworkDevice {
             .stdout;
 
         if iw_output.is_empty() {
-            iw_output = Command::new("nmcli")
+            iw_output = stats::command("nmcli")
                 .args(&["-g", "general.connection", "device", "show", &self.device])
                 .output()
                 .block_error("net", "Failed to execute SSID query.")?
@@ -136,7 +136,7 @@ impl NetworkDevice {
         if !self.is_up()? {
             return Ok(None);
         }
-        let mut ip_output = Command::new("sh")
+        let mut ip_output = stats::command("sh")
             .args(
                 &[
                     "-c",
@@ -170,7 +170,7 @@ impl NetworkDevice {
                     .to_string(),
             ));
         }
-        let mut bitrate_output = Command::new("sh")
+        let mut bitrate_output = stats::command("sh")
             .args(&[
                 "-c",
                 &format!(
@@ -313,7 +313,7 @@ impl ConfigBlock for Net {
     type Config = NetConfig;
 
     fn new(block_config: Self::Config, config: Config, _tx_update_request: Sender<Task>) -> Result<Self> {
-        let device = NetworkDevice::from_device(block_config.device)?;
+        let device = NetworkDevice::from_device(block_config.device, &config.roots)?;
         let init_rx_bytes = device.rx_bytes()?;
         let init_tx_bytes = device.tx_bytes()?;
         let wireless = device.is_wireless();
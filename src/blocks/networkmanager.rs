@@ -1,17 +1,17 @@
 use std::fmt;
 use std::time::{Duration, Instant};
-use std::thread;
 
 use chan::Sender;
 use uuid::Uuid;
 
 use config::Config;
 use errors::*;
+use dbus_dispatcher::{self, Bus, MatchRule, Message};
+use listener::DISCONNECTED;
 use scheduler::Task;
 use block::{Block, ConfigBlock};
 use widget::{I3BarWidget, State};
 use widgets::text::TextWidget;
-use blocks::dbus::{BusType, Connection, Message, MessageItem};
 use blocks::dbus::arg::Variant;
 
 enum NetworkState {
@@ -91,25 +91,18 @@ impl ConnectionManager {
         ConnectionManager {}
     }
 
-    fn get_property(c: &Connection, property: &str) -> Result<Message> {
-        let m = Message::new_method_call(
+    fn get_property(property: &str) -> Result<Message> {
+        dbus_dispatcher::get_property(
+            Bus::System,
             "org.freedesktop.NetworkManager",
             "/org/freedesktop/NetworkManager",
-            "org.freedesktop.DBus.Properties",
-            "Get")
-            .block_error("networkmanager", "Failed to create message")?
-            .append2(
-                MessageItem::Str("org.freedesktop.NetworkManager".to_string()),
-                MessageItem::Str(property.to_string())
-            );
-
-        let r = c.send_with_reply_and_block(m, 1000);
-
-        r.block_error("networkmanager", "Failed to retrieve property")
+            "org.freedesktop.NetworkManager",
+            property,
+        ).block_error("networkmanager", "Failed to retrieve property")
     }
 
-    pub fn state(&self, c: &Connection) -> Result<NetworkState> {
-        let m = Self::get_property(c, "State")?;
+    pub fn state(&self) -> Result<NetworkState> {
+        let m = Self::get_property("State")?;
 
         let state: Variant<u32> = m.get1()
             .block_error("networkmanager", "Failed to read property")?;
@@ -117,8 +110,8 @@ impl ConnectionManager {
         Ok(NetworkState::from(state.0))
     }
 
-    pub fn connection_type(&self, c: &Connection) -> Result<ConnectionType> {
-        let m = Self::get_property(c, "PrimaryConnectionType")?;
+    pub fn connection_type(&self) -> Result<ConnectionType> {
+        let m = Self::get_property("PrimaryConnectionType")?;
 
         let connection_type: Variant<String> = m.get1()
             .block_error("networkmanager", "Failed to read property")?;
@@ -130,7 +123,6 @@ impl ConnectionManager {
 pub struct NetworkManager {
     id: String,
     output: TextWidget,
-    dbus_conn: Connection,
     manager: ConnectionManager,
     show_type: bool,
 }
@@ -155,35 +147,26 @@ impl ConfigBlock for NetworkManager {
     fn new(block_config: Self::Config, config: Config, send: Sender<Task>) -> Result<Self> {
         let id: String = Uuid::new_v4().simple().to_string();
         let id_copy = id.clone();
-        let dbus_conn = Connection::get_private(BusType::System)
-            .block_error("networkmanager", "failed to establish D-Bus connection")?;
         let manager = ConnectionManager::new();
 
-        thread::spawn(move || {
-            let c = Connection::get_private(BusType::System).unwrap();
-            let rule = "type='signal',\
-                 path='/org/freedesktop/NetworkManager',\
-                 interface='org.freedesktop.NetworkManager',\
-                 member='StateChanged'";
-
-            c.add_match(&rule).unwrap();
-
-            loop {
-                let timeout = 100_000;
-
-                for _event in c.iter(timeout) {
-                    send.send(Task {
-                        id: id.clone(),
-                        update_time: Instant::now(),
-                    });
-                }
-            }
-        });
+        // State changes, and the connection to the bus going away or coming back
+        dbus_dispatcher::subscribe(
+            Bus::System,
+            MatchRule::new()
+                .with_path("/org/freedesktop/NetworkManager")
+                .with_interface("org.freedesktop.NetworkManager")
+                .with_member("StateChanged"),
+            move |_| {
+                send.send(Task {
+                    id: id.clone(),
+                    update_time: Instant::now(),
+                })
+            },
+        )?;
 
         Ok(NetworkManager {
             id: id_copy,
             output: TextWidget::new(config),
-            dbus_conn,
             manager,
             show_type: block_config.show_type,
         })
@@ -196,8 +179,14 @@ impl Block for NetworkManager {
     }
 
     fn update(&mut self) -> Result<Option<Duration>> {
-        let state = self.manager.state(&self.dbus_conn)?;
-        let connection_type = self.manager.connection_type(&self.dbus_conn)?;
+        if !dbus_dispatcher::is_connected(Bus::System) {
+            self.output.set_text(DISCONNECTED.to_owned());
+            self.output.set_state(State::Idle);
+            return Ok(None);
+        }
+
+        let state = self.manager.state()?;
+        let connection_type = self.manager.connection_type()?;
 
         self.output.set_icon(&connection_type.to_string());
         self.output.set_state(match state {
@@ -213,6 +202,8 @@ impl Block for NetworkManager {
 
         if self.show_type {
             self.output.set_text(state.to_string());
+        } else {
+            self.output.set_text(String::new());
         }
 
         Ok(None)
@@ -1,6 +1,6 @@
 use std::env;
 use std::time::Duration;
-use std::process::Command;
+use stats;
 use chan::Sender;
 use scheduler::Task;
 
@@ -132,7 +132,7 @@ impl Block for NightLight {
                             if self.color_temperature > 25000 {
                                 self.color_temperature = 25000;
                             }
-                            Command::new(env::var("SHELL").unwrap_or("sh".to_owned()))
+                            stats::command(env::var("SHELL").unwrap_or("sh".to_owned()))
                                 .args(&["-c", &self.command_on.replace("4500", &self.color_temperature.to_string())])
                                 .output()
                                 .block_error("toggle", "failed to run toggle command")?;
@@ -149,7 +149,7 @@ impl Block for NightLight {
                             if self.color_temperature < 1000 {
                                 self.color_temperature = 1000;
                             }
-                            Command::new(env::var("SHELL").unwrap_or("sh".to_owned()))
+                            stats::command(env::var("SHELL").unwrap_or("sh".to_owned()))
                                 .args(&["-c", &self.command_on.replace("4500", &self.color_temperature.to_string())])
                                 .output()
                                 .block_error("toggle", "failed to run toggle command")?;
@@ -176,7 +176,7 @@ impl Block for NightLight {
                             &self.command_on
                         };
 
-                        Command::new(env::var("SHELL").unwrap_or("sh".to_owned()))
+                        stats::command(env::var("SHELL").unwrap_or("sh".to_owned()))
                             .args(&["-c", cmd])
                             .output()
                             .block_error("toggle", "failed to run toggle command")?;
@@ -1,5 +1,5 @@
 use std::time::Duration;
-use std::process::Command;
+use stats;
 use chan::Sender;
 
 use block::{Block, ConfigBlock};
@@ -112,7 +112,7 @@ impl ConfigBlock for NvidiaGpu {
         let id = Uuid::new_v4().simple().to_string();
         let id_memory = Uuid::new_v4().simple().to_string();
         let id_fans = Uuid::new_v4().simple().to_string();
-        let mut output = Command::new("nvidia-smi")
+        let mut output = stats::command("nvidia-smi")
             .args(
                 &[
                     "-i", &block_config.gpu_id.to_string(),
@@ -185,7 +185,7 @@ impl Block for NvidiaGpu {
             params += "clocks.current.graphics,";
         }
 
-        let mut output = Command::new("nvidia-smi")
+        let mut output = stats::command("nvidia-smi")
             .args(
                 &[
                     "-i", &self.gpu_id.to_string(),
@@ -293,7 +293,7 @@ impl Block for NvidiaGpu {
                     if self.memory_total_displayed {
                         memory_widget.set_text(format!("{}MB", self.memory_total));
                     } else {
-                        let mut output = Command::new("nvidia-smi")
+                        let mut output = stats::command("nvidia-smi")
                             .args(
                                 &[
                                     "-i", &self.gpu_id.to_string(),
@@ -335,7 +335,7 @@ impl Block for NvidiaGpu {
                 if let Some(ref mut fan_widget) = self.show_fan {
                     if controlled_changed {
                         if self.fan_speed_controlled {
-                            Command::new("nvidia-settings")
+                            stats::command("nvidia-settings")
                                 .args(
                                     &[
                                         "-a",
@@ -352,7 +352,7 @@ impl Block for NvidiaGpu {
                             fan_widget.set_text(format!("{:02}%", self.fan_speed));
                             fan_widget.set_state(State::Warning);
                         } else {
-                            Command::new("nvidia-settings")
+                            stats::command("nvidia-settings")
                                 .args(
                                     &[
                                         "-a",
@@ -365,7 +365,7 @@ impl Block for NvidiaGpu {
                             fan_widget.set_state(State::Idle);
                         }
                     } else if self.fan_speed_controlled {
-                        Command::new("nvidia-settings")
+                        stats::command("nvidia-settings")
                             .args(
                                 &[
                                     "-a",
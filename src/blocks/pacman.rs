@@ -2,7 +2,7 @@ use std::fs;
 use std::path::Path;
 use std::os::unix::fs::symlink;
 use std::time::Duration;
-use std::process::Command;
+use stats;
 use std::env;
 use std::ffi::OsString;
 use chan::Sender;
@@ -51,7 +51,7 @@ impl ConfigBlock for Pacman {
 }
 
 fn run_command(var: &str) -> Result<()> {
-    Command::new("sh")
+    stats::command("sh")
         .args(&["-c", var])
         .spawn()
         .block_error("pacman", &format!("Failed to run command '{}'", var))?
@@ -63,7 +63,7 @@ fn run_command(var: &str) -> Result<()> {
 fn has_fake_root() -> Result<bool> {
     Ok(
         String::from_utf8(
-            Command::new("sh")
+            stats::command("sh")
                 .args(&["-c", "type -P fakeroot"])
                 .output()
                 .block_error("pacman", "failed to start command to check for fakeroot")?
@@ -119,7 +119,7 @@ fn get_update_count() -> Result<usize> {
     // Get update count
     Ok(
         String::from_utf8(
-            Command::new("sh")
+            stats::command("sh")
                 .env("LC_ALL", "C")
                 .args(&[
                     "-c",
@@ -0,0 +1,128 @@
+//! A block showing the resource usage of i3status-rs itself.
+
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use chan::Sender;
+use uuid::Uuid;
+
+use block::{Block, ConfigBlock};
+use config::Config;
+use de::deserialize_duration;
+use errors::*;
+use scheduler::Task;
+use util::{read_file, FormatTemplate};
+use widget::I3BarWidget;
+use widgets::text::TextWidget;
+
+/// Clock ticks per second in `/proc/<pid>/stat`, which Linux fixes at 100 for userspace.
+const USER_HZ: f64 = 100f64;
+
+pub struct SelfStats {
+    text: TextWidget,
+    id: String,
+    format: FormatTemplate,
+    update_interval: Duration,
+    /// CPU time used so far in ticks, and when it was read.
+    last_cpu: Option<(u64, Instant)>,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SelfStatsConfig {
+    /// Format override
+    #[serde(default = "SelfStatsConfig::default_format")]
+    pub format: String,
+
+    /// Update interval in seconds
+    #[serde(default = "SelfStatsConfig::default_interval", deserialize_with = "deserialize_duration")]
+    pub interval: Duration,
+}
+
+impl SelfStatsConfig {
+    fn default_format() -> String {
+        "{cpu}% {rss}MiB".to_owned()
+    }
+
+    fn default_interval() -> Duration {
+        Duration::from_secs(5)
+    }
+}
+
+impl ConfigBlock for SelfStats {
+    type Config = SelfStatsConfig;
+
+    fn new(block_config: Self::Config, config: Config, _tx_update_request: Sender<Task>) -> Result<Self> {
+        Ok(SelfStats {
+            id: Uuid::new_v4().simple().to_string(),
+            text: TextWidget::new(config),
+            format: FormatTemplate::from_string(&block_config.format)
+                .block_error("self", "invalid format specified for self")?,
+            update_interval: block_config.interval,
+            last_cpu: None,
+        })
+    }
+}
+
+/// User and system time used by this process, in ticks.
+fn cpu_ticks() -> Result<u64> {
+    let stat = read_file("self", Path::new("/proc/self/stat"))?;
+    // The command name may contain spaces, so only split what follows it
+    let fields: Vec<&str> = stat.rsplitn(2, ')')
+        .next()
+        .block_error("self", "invalid /proc/self/stat")?
+        .split_whitespace()
+        .collect();
+    // utime and stime are fields 14 and 15, the first after the name is field 3
+    if fields.len() < 13 {
+        return Err(BlockError("self".to_owned(), "invalid /proc/self/stat".to_owned()));
+    }
+    let utime = fields[11].parse::<u64>().block_error("self", "invalid utime")?;
+    let stime = fields[12].parse::<u64>().block_error("self", "invalid stime")?;
+    Ok(utime + stime)
+}
+
+/// Resident set size of this process, in KiB.
+fn rss_kib() -> Result<u64> {
+    let status = read_file("self", Path::new("/proc/self/status"))?;
+    status
+        .lines()
+        .find(|line| line.starts_with("VmRSS:"))
+        .and_then(|line| line.split_whitespace().nth(1))
+        .and_then(|value| value.parse::<u64>().ok())
+        .block_error("self", "failed to read VmRSS from /proc/self/status")
+}
+
+impl Block for SelfStats {
+    fn update(&mut self) -> Result<Option<Duration>> {
+        let ticks = cpu_ticks()?;
+        let now = Instant::now();
+        let cpu = match self.last_cpu {
+            Some((last_ticks, last_time)) => {
+                let elapsed = now.duration_since(last_time);
+                let seconds = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1_000_000_000f64;
+                if seconds > 0f64 {
+                    (ticks - last_ticks) as f64 / USER_HZ / seconds * 100f64
+                } else {
+                    0f64
+                }
+            }
+            None => 0f64,
+        };
+        self.last_cpu = Some((ticks, now));
+
+        let values = map!("{cpu}" => format!("{:02.0}", cpu),
+                          "{rss}" => format!("{:.1}", rss_kib()? as f64 / 1024f64));
+        self.text.set_text(self.format.render_static_str(&values)?);
+
+        Ok(Some(self.update_interval))
+    }
+
+    fn view(&self) -> Vec<&I3BarWidget> {
+        vec![&self.text]
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
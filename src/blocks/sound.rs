@@ -1,8 +1,8 @@
-#[cfg(feature = "pulseaudio")]
 use std::cmp::min;
 use std::cmp::max;
 use std::io::Read;
-use std::process::{Command, Stdio};
+use std::process::Stdio;
+use stats;
 use std::thread;
 use std::time::{Duration, Instant};
 use std::ffi::OsStr;
@@ -13,6 +13,7 @@ use std::cell::RefCell;
 #[cfg(feature = "pulseaudio")]
 use std::sync::Mutex;
 #[cfg(feature = "pulseaudio")]
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 #[cfg(feature = "pulseaudio")]
 use std::ops::Deref;
@@ -24,16 +25,23 @@ use scheduler::Task;
 use block::{Block, ConfigBlock};
 use config::Config;
 use errors::*;
+use logging::spawn_thread;
+use util::{FormatTemplate, IconRamp};
 use widgets::button::ButtonWidget;
 use widget::{I3BarWidget, State};
 use input::{I3BarEvent, MouseButton};
 
+#[cfg(feature = "native-alsa")]
+use alsa::mixer::{MilliBel, Mixer, Round, Selem, SelemChannelId, SelemId};
+#[cfg(feature = "native-alsa")]
+use alsa::poll::{poll, PollDescriptors};
+
 #[cfg(feature = "pulseaudio")]
 use pulse::mainloop::standard::Mainloop;
 #[cfg(feature = "pulseaudio")]
 use pulse::callbacks::ListResult;
 #[cfg(feature = "pulseaudio")]
-use pulse::context::{Context, flags, State as PulseState, introspect::SinkInfo, introspect::ServerInfo, subscribe::Facility, subscribe::Operation as SubscribeOperation, subscribe::subscription_masks};
+use pulse::context::{Context, flags, State as PulseState, introspect::SinkInfo, introspect::SinkInputInfo, introspect::SourceInfo, introspect::ServerInfo, subscribe::Facility, subscribe::Operation as SubscribeOperation, subscribe::subscription_masks};
 #[cfg(feature = "pulseaudio")]
 use pulse::proplist::{properties, Proplist};
 #[cfg(feature = "pulseaudio")]
@@ -43,27 +51,66 @@ use pulse::volume::{ChannelVolumes, VOLUME_NORM, VOLUME_MAX};
 
 use uuid::Uuid;
 
+/// An application playing sound, as shown with `show_streams`.
+struct Stream {
+    index: u32,
+    name: String,
+    muted: bool,
+}
+
 trait SoundDevice {
     fn volume(&self) -> u32;
     fn muted(&self) -> bool;
 
+    /// The volume in dB, if the driver knows it.
+    fn db(&self) -> Option<f64> {
+        None
+    }
+
     fn get_info(&mut self) -> Result<()>;
     fn set_volume(&mut self, step: i32) -> Result<()>;
     fn toggle(&mut self) -> Result<()>;
     fn monitor(&mut self, id: String, tx_update_request: Sender<Task>) -> Result<()>;
+
+    /// The applications currently playing sound, if the driver knows them.
+    fn streams(&self) -> Vec<Stream> {
+        Vec::new()
+    }
+
+    fn toggle_stream(&mut self, _index: u32) -> Result<()> {
+        Err(BlockError("sound".into(), "streams are not supported by this driver".into()))
+    }
+
+    /// The name of the device, and its description if the driver has one.
+    fn output_name(&self) -> (String, Option<String>);
+
+    /// Makes the next device the default one, if the driver supports it.
+    fn next_output(&mut self) -> Result<()> {
+        Err(BlockError("sound".into(), "switching devices is not supported by this driver".into()))
+    }
 }
 
+// Only a stand-in for the disabled drivers when the native ALSA one is built
+#[cfg_attr(feature = "native-alsa", allow(dead_code))]
 struct AlsaSoundDevice {
     name: String,
+    card: String,
+    /// The control as amixer expects it, e.g. `Master,0`
+    control: String,
     volume: u32,
+    db: Option<f64>,
     muted: bool,
 }
 
+#[cfg_attr(feature = "native-alsa", allow(dead_code))]
 impl AlsaSoundDevice {
-    fn new(name: String) -> Result<Self> {
+    fn new(name: String, card: String, index: u32) -> Result<Self> {
         let mut sd = AlsaSoundDevice {
+            control: format!("{},{}", name, index),
             name,
+            card,
             volume: 0,
+            db: None,
             muted: false,
         };
         sd.get_info()?;
@@ -75,10 +122,11 @@ impl AlsaSoundDevice {
 impl SoundDevice for AlsaSoundDevice {
     fn volume(&self) -> u32 { self.volume }
     fn muted(&self) -> bool { self.muted }
+    fn db(&self) -> Option<f64> { self.db }
 
     fn get_info(&mut self) -> Result<()> {
-        let output = Command::new("amixer")
-            .args(&["get", &self.name])
+        let output = stats::command("amixer")
+            .args(&["-D", &self.card, "get", &self.control])
             .output()
             .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
             .block_error("sound", "could not run amixer to get sound info")?;
@@ -101,16 +149,24 @@ impl SoundDevice for AlsaSoundDevice {
 
         self.muted = last.get(1).map(|muted| *muted == "off").unwrap_or(false);
 
+        // Controls with a dB scale also show e.g. [-12.00dB]
+        self.db = last_line
+            .split_whitespace()
+            .find(|x| x.starts_with('[') && x.ends_with("dB]"))
+            .and_then(|db| db.trim_matches(FILTER).trim_right_matches("dB").parse::<f64>().ok());
+
         Ok(())
     }
 
     fn set_volume(&mut self, step: i32) -> Result<()> {
         let volume = max(0, self.volume as i32 + step) as u32;
 
-        Command::new("amixer")
+        stats::command("amixer")
             .args(&[
+                "-D",
+                &self.card,
                 "set",
-                &self.name,
+                &self.control,
                 &format!("{}%", volume),
             ])
             .output()
@@ -121,9 +177,13 @@ impl SoundDevice for AlsaSoundDevice {
         Ok(())
     }
 
+    fn output_name(&self) -> (String, Option<String>) {
+        (self.name.clone(), None)
+    }
+
     fn toggle(&mut self) -> Result<()> {
-        Command::new("amixer")
-            .args(&["set", &self.name, "toggle"])
+        stats::command("amixer")
+            .args(&["-D", &self.card, "set", &self.control, "toggle"])
             .output()
             .block_error("sound", "failed to toggle mute")?;
 
@@ -134,15 +194,16 @@ impl SoundDevice for AlsaSoundDevice {
 
     fn monitor(&mut self, id: String, tx_update_request: Sender<Task>) -> Result<()> {
         // Monitor volume changes in a separate thread.
-        thread::spawn(move || {
+        spawn_thread("sound", move || {
             // Line-buffer to reduce noise.
-            let mut monitor = Command::new("stdbuf")
+            let mut monitor = stats::command("stdbuf")
+                .block(&id)
                 .args(&["-oL", "alsactl", "monitor"])
                 .stdout(Stdio::piped())
                 .spawn()
-                .expect("Failed to start alsactl monitor")
+                .block_error("sound", "failed to start alsactl monitor")?
                 .stdout
-                .expect("Failed to pipe alsactl monitor output");
+                .block_error("sound", "failed to pipe alsactl monitor output")?;
 
             let mut buffer = [0; 1024]; // Should be more than enough.
             loop {
@@ -165,6 +226,233 @@ impl SoundDevice for AlsaSoundDevice {
     }
 }
 
+/// A simple mixer control, talked to through alsa-lib instead of amixer.
+#[cfg(feature = "native-alsa")]
+struct AlsaMixerSoundDevice {
+    name: String,
+    index: u32,
+    card: String,
+    device_kind: DeviceKind,
+    volume_scale: VolumeScale,
+    mixer: Mixer,
+    /// The volume of each channel of the control, in percent
+    volumes: Vec<(SelemChannelId, u32)>,
+    db: Option<f64>,
+    muted: bool,
+}
+
+#[cfg(feature = "native-alsa")]
+impl AlsaMixerSoundDevice {
+    fn new(name: String, card: String, index: u32, device_kind: DeviceKind, volume_scale: VolumeScale) -> Result<Self> {
+        let mixer = Mixer::new(&card, false)
+            .block_error("sound", &format!("could not open the mixer of ALSA card '{}'", card))?;
+
+        let mut sd = AlsaMixerSoundDevice {
+            name,
+            index,
+            card,
+            device_kind,
+            volume_scale,
+            mixer,
+            volumes: Vec::new(),
+            db: None,
+            muted: false,
+        };
+        sd.get_info()?;
+
+        Ok(sd)
+    }
+
+    fn selem(&self) -> Result<Selem> {
+        self.mixer
+            .find_selem(&SelemId::new(&self.name, self.index))
+            .block_error("sound", &format!("could not find ALSA mixer control '{},{}'", self.name, self.index))
+    }
+
+    /// The channels of the control, with their volume in percent.
+    fn read_volumes(&self, selem: &Selem) -> Result<Vec<(SelemChannelId, u32)>> {
+        let mut volumes = Vec::new();
+        for &channel in SelemChannelId::all() {
+            let (has_channel, value, (min, max)) = match (self.device_kind, self.volume_scale) {
+                (DeviceKind::Sink, VolumeScale::Linear) => (
+                    selem.has_playback_channel(channel),
+                    selem.get_playback_volume(channel),
+                    selem.get_playback_volume_range(),
+                ),
+                (DeviceKind::Source, VolumeScale::Linear) => (
+                    selem.has_capture_channel(channel),
+                    selem.get_capture_volume(channel),
+                    selem.get_capture_volume_range(),
+                ),
+                (DeviceKind::Sink, VolumeScale::Db) => {
+                    let (min, max) = selem.get_playback_db_range();
+                    (
+                        selem.has_playback_channel(channel),
+                        selem.get_playback_vol_db(channel).map(|db| db.0),
+                        (min.0, max.0),
+                    )
+                }
+                (DeviceKind::Source, VolumeScale::Db) => {
+                    let (min, max) = selem.get_capture_db_range();
+                    (
+                        selem.has_capture_channel(channel),
+                        selem.get_capture_vol_db(channel).map(|db| db.0),
+                        (min.0, max.0),
+                    )
+                }
+            };
+            if !has_channel {
+                continue;
+            }
+
+            let value = value.block_error("sound", "could not get volume")?;
+            let volume = if max > min {
+                ((value - min) * 100 + (max - min) / 2) / (max - min)
+            } else {
+                0
+            };
+            volumes.push((channel, volume as u32));
+        }
+        Ok(volumes)
+    }
+
+    fn write_volume(&self, selem: &Selem, channel: SelemChannelId, volume: u32) -> Result<()> {
+        let volume = i64::from(volume);
+        let result = match (self.device_kind, self.volume_scale) {
+            (DeviceKind::Sink, VolumeScale::Linear) => {
+                let (min, max) = selem.get_playback_volume_range();
+                selem.set_playback_volume(channel, min + (volume * (max - min) + 50) / 100)
+            }
+            (DeviceKind::Source, VolumeScale::Linear) => {
+                let (min, max) = selem.get_capture_volume_range();
+                selem.set_capture_volume(channel, min + (volume * (max - min) + 50) / 100)
+            }
+            (DeviceKind::Sink, VolumeScale::Db) => {
+                let (min, max) = selem.get_playback_db_range();
+                selem.set_playback_db(channel, MilliBel(min.0 + volume * (max.0 - min.0) / 100), Round::Floor)
+            }
+            (DeviceKind::Source, VolumeScale::Db) => {
+                let (min, max) = selem.get_capture_db_range();
+                selem.set_capture_db(channel, MilliBel(min.0 + volume * (max.0 - min.0) / 100), Round::Floor)
+            }
+        };
+        result.block_error("sound", "failed to set volume")
+    }
+}
+
+#[cfg(feature = "native-alsa")]
+impl SoundDevice for AlsaMixerSoundDevice {
+    fn volume(&self) -> u32 {
+        if self.volumes.is_empty() {
+            return 0;
+        }
+        self.volumes.iter().map(|&(_, volume)| volume).sum::<u32>() / self.volumes.len() as u32
+    }
+
+    fn muted(&self) -> bool { self.muted }
+    fn db(&self) -> Option<f64> { self.db }
+
+    fn get_info(&mut self) -> Result<()> {
+        // The mixer only learns about changes by handling its pending events
+        self.mixer.handle_events().block_error("sound", "could not read ALSA mixer events")?;
+
+        let (volumes, db, muted) = {
+            let selem = self.selem()?;
+            let volumes = self.read_volumes(&selem)?;
+            let dbs: Vec<f64> = volumes
+                .iter()
+                .filter_map(|&(channel, _)| {
+                    let db = match self.device_kind {
+                        DeviceKind::Sink => selem.get_playback_vol_db(channel),
+                        DeviceKind::Source => selem.get_capture_vol_db(channel),
+                    };
+                    db.ok().map(|db| db.0 as f64 / 100.0)
+                })
+                .collect();
+            let db = if dbs.is_empty() {
+                None
+            } else {
+                Some(dbs.iter().sum::<f64>() / dbs.len() as f64)
+            };
+            // Controls without a switch can't be muted; a switch that is off means muted
+            let muted = match self.device_kind {
+                DeviceKind::Sink if selem.has_playback_switch() => volumes
+                    .iter()
+                    .any(|&(channel, _)| selem.get_playback_switch(channel).map(|on| on == 0).unwrap_or(false)),
+                DeviceKind::Source if selem.has_capture_switch() => volumes
+                    .iter()
+                    .any(|&(channel, _)| selem.get_capture_switch(channel).map(|on| on == 0).unwrap_or(false)),
+                _ => false,
+            };
+            (volumes, db, muted)
+        };
+        self.volumes = volumes;
+        self.db = db;
+        self.muted = muted;
+
+        Ok(())
+    }
+
+    fn set_volume(&mut self, step: i32) -> Result<()> {
+        // Each channel is stepped on its own, which keeps the balance between them
+        let volumes: Vec<(SelemChannelId, u32)> = self.volumes
+            .iter()
+            .map(|&(channel, volume)| (channel, min(100, max(0, volume as i32 + step)) as u32))
+            .collect();
+        {
+            let selem = self.selem()?;
+            for &(channel, volume) in &volumes {
+                self.write_volume(&selem, channel, volume)?;
+            }
+        }
+        self.volumes = volumes;
+
+        Ok(())
+    }
+
+    fn output_name(&self) -> (String, Option<String>) {
+        (self.name.clone(), None)
+    }
+
+    fn toggle(&mut self) -> Result<()> {
+        let on = if self.muted { 1 } else { 0 };
+        {
+            let selem = self.selem()?;
+            let result = match self.device_kind {
+                DeviceKind::Sink => selem.set_playback_switch_all(on),
+                DeviceKind::Source => selem.set_capture_switch_all(on),
+            };
+            result.block_error("sound", "failed to toggle mute")?;
+        }
+        self.muted = !self.muted;
+
+        Ok(())
+    }
+
+    fn monitor(&mut self, id: String, tx_update_request: Sender<Task>) -> Result<()> {
+        let card = self.card.clone();
+        // Mixers can't be shared between threads, so the monitor opens its own.
+        spawn_thread("sound", move || {
+            let mixer = Mixer::new(&card, false)
+                .block_error("sound", &format!("could not open the mixer of ALSA card '{}'", card))?;
+
+            loop {
+                // Block until the mixer has events for us, i.e. something changed
+                let mut fds = PollDescriptors::get(&mixer).block_error("sound", "could not get ALSA mixer descriptors")?;
+                poll(&mut fds, -1).block_error("sound", "failed to wait for ALSA mixer events")?;
+                mixer.handle_events().block_error("sound", "could not read ALSA mixer events")?;
+
+                tx_update_request.send(Task {
+                    id: id.clone(),
+                    update_time: Instant::now(),
+                });
+            }
+        });
+
+        Ok(())
+    }
+}
+
 #[cfg(feature = "pulseaudio")]
 struct PulseAudioConnection {
     mainloop: Rc<RefCell<Mainloop>>,
@@ -178,27 +466,50 @@ struct PulseAudioClient {
 
 #[cfg(feature = "pulseaudio")]
 struct PulseAudioSoundDevice {
+    kind: DeviceKind,
     name: Option<String>,
     volume: Option<ChannelVolumes>,
     volume_avg: u32,
     muted: bool,
 }
 
+/// The state of a sink or source.
 #[cfg(feature = "pulseaudio")]
 #[derive(Debug)]
-struct PulseAudioSinkInfo {
+struct PulseAudioDeviceInfo {
+    index: u32,
+    /// The description of the device, or of its active port if it has several.
+    description: Option<String>,
     volume: ChannelVolumes,
     mute: bool,
 }
 
+#[cfg(feature = "pulseaudio")]
+#[derive(Debug)]
+struct PulseAudioSinkInputInfo {
+    name: String,
+    mute: bool,
+    corked: bool,
+}
+
 #[cfg(feature = "pulseaudio")]
 #[derive(Debug)]
 enum PulseAudioClientRequest {
     GetDefaultDevice,
     GetSinkInfoByIndex(u32),
     GetSinkInfoByName(String),
+    GetSinkInfoList,
+    SetDefaultSink(String),
+    MoveSinkInput(u32, String),
     SetSinkVolumeByName(String, ChannelVolumes),
     SetSinkMuteByName(String, bool),
+    GetSourceInfoByIndex(u32),
+    GetSourceInfoByName(String),
+    SetSourceVolumeByName(String, ChannelVolumes),
+    SetSourceMuteByName(String, bool),
+    GetSinkInputInfo(u32),
+    GetSinkInputInfoList,
+    SetSinkInputMute(u32, bool),
 }
 
 #[cfg(feature = "pulseaudio")]
@@ -206,7 +517,10 @@ lazy_static! {
     static ref PULSEAUDIO_CLIENT: Result<PulseAudioClient> = PulseAudioClient::new();
     static ref PULSEAUDIO_EVENT_LISTENER: Mutex<HashMap<String, Sender<Task>>> = Mutex::new(HashMap::new());
     static ref PULSEAUDIO_DEFAULT_SINK: Mutex<String> = Mutex::new("@DEFAULT_SINK@".into());
-    static ref PULSEAUDIO_SINKS: Mutex<HashMap<String, PulseAudioSinkInfo>> = Mutex::new(HashMap::new());
+    static ref PULSEAUDIO_DEFAULT_SOURCE: Mutex<String> = Mutex::new("@DEFAULT_SOURCE@".into());
+    static ref PULSEAUDIO_SINKS: Mutex<HashMap<String, PulseAudioDeviceInfo>> = Mutex::new(HashMap::new());
+    static ref PULSEAUDIO_SOURCES: Mutex<HashMap<String, PulseAudioDeviceInfo>> = Mutex::new(HashMap::new());
+    static ref PULSEAUDIO_SINK_INPUTS: Mutex<BTreeMap<u32, PulseAudioSinkInputInfo>> = Mutex::new(BTreeMap::new());
 }
 
 #[cfg(feature = "pulseaudio")]
@@ -243,10 +557,10 @@ impl PulseAudioConnection {
                 PulseState::Ready => { break; },
                 PulseState::Failed |
                 PulseState::Terminated => {
-                    Err(BlockError(
+                    return Err(BlockError(
                         "sound".into(),
                         "pulseaudio context state failed/terminated".into(),
-                    )).unwrap()
+                    ));
                 },
                 _ => {},
             }
@@ -275,16 +589,16 @@ impl PulseAudioClient {
         let (send_req, recv_req) = async();
         let (send_result, recv_result) = sync(0);
         let send_result2 = send_result.clone();
-        let new_connection = |sender: Sender<Result<()>>| -> PulseAudioConnection {
-            let conn = PulseAudioConnection::new();
-            match conn {
+        // Failing to connect is reported to the block through `sender`
+        let new_connection = |sender: Sender<Result<()>>| -> Option<PulseAudioConnection> {
+            match PulseAudioConnection::new() {
                 Ok(conn) => {
                     sender.send(Ok(()));
-                    conn
+                    Some(conn)
                 },
                 Err(err) => {
                     sender.send(Err(err));
-                    panic!("failed to create pulseaudio connection");
+                    None
                 }
             }
         };
@@ -301,13 +615,16 @@ impl PulseAudioClient {
         };
 
         // requests
-        thread::spawn(move || {
-            let mut connection = new_connection(send_result);
+        spawn_thread("sound", move || {
+            let mut connection = match new_connection(send_result) {
+                Some(connection) => connection,
+                None => return Ok(()),
+            };
 
             loop {
                 // make sure mainloop dispatched everything
                 for _ in 0..10 {
-                    connection.iterate(false).unwrap();
+                    connection.iterate(false)?;
                 }
 
                 match recv_req.recv() {
@@ -325,17 +642,47 @@ impl PulseAudioClient {
                             PulseAudioClientRequest::GetSinkInfoByName(name) => {
                                 introspector.get_sink_info_by_name(&name, PulseAudioClient::sink_info_callback);
                             },
+                            PulseAudioClientRequest::GetSinkInfoList => {
+                                introspector.get_sink_info_list(PulseAudioClient::sink_info_callback);
+                            },
+                            PulseAudioClientRequest::SetDefaultSink(name) => {
+                                connection.context.borrow_mut().set_default_sink(&name, |_| {});
+                            },
+                            PulseAudioClientRequest::MoveSinkInput(index, name) => {
+                                introspector.move_sink_input_by_name(index, &name, None);
+                            },
                             PulseAudioClientRequest::SetSinkVolumeByName(name, volumes) => {
                                 introspector.set_sink_volume_by_name(&name, &volumes, None);
                             },
                             PulseAudioClientRequest::SetSinkMuteByName(name, mute) => {
                                 introspector.set_sink_mute_by_name(&name, mute, None);
                             },
+                            PulseAudioClientRequest::GetSourceInfoByIndex(index) => {
+                                introspector.get_source_info_by_index(index, PulseAudioClient::source_info_callback);
+                            },
+                            PulseAudioClientRequest::GetSourceInfoByName(name) => {
+                                introspector.get_source_info_by_name(&name, PulseAudioClient::source_info_callback);
+                            },
+                            PulseAudioClientRequest::SetSourceVolumeByName(name, volumes) => {
+                                introspector.set_source_volume_by_name(&name, &volumes, None);
+                            },
+                            PulseAudioClientRequest::SetSourceMuteByName(name, mute) => {
+                                introspector.set_source_mute_by_name(&name, mute, None);
+                            },
+                            PulseAudioClientRequest::GetSinkInputInfo(index) => {
+                                introspector.get_sink_input_info(index, PulseAudioClient::sink_input_info_callback);
+                            },
+                            PulseAudioClientRequest::GetSinkInputInfoList => {
+                                introspector.get_sink_input_info_list(PulseAudioClient::sink_input_info_callback);
+                            },
+                            PulseAudioClientRequest::SetSinkInputMute(index, mute) => {
+                                introspector.set_sink_input_mute(index, mute, None);
+                            },
                         };
 
                         // send request and receive response
-                        connection.iterate(true).unwrap();
-                        connection.iterate(true).unwrap();
+                        connection.iterate(true)?;
+                        connection.iterate(true)?;
                     }
                 }
             }
@@ -343,18 +690,28 @@ impl PulseAudioClient {
         thread_result()?;
 
         // subscribe
-        thread::spawn(move || {
-            let connection = new_connection(send_result2);
+        spawn_thread("sound", move || {
+            let connection = match new_connection(send_result2) {
+                Some(connection) => connection,
+                None => return Ok(()),
+            };
         
             // subcribe for events
             connection.context.borrow_mut().set_subscribe_callback(Some(Box::new(PulseAudioClient::subscribe_callback)));
             connection.context.borrow_mut().subscribe(
                 subscription_masks::SERVER |
-                subscription_masks::SINK,
+                subscription_masks::SINK |
+                subscription_masks::SOURCE |
+                subscription_masks::SINK_INPUT,
                 |_| { }
             );
 
-            connection.mainloop.borrow_mut().run().unwrap();
+            connection
+                .mainloop
+                .borrow_mut()
+                .run()
+                .block_error("sound", "pulseaudio mainloop failed")?;
+            Ok(())
         });
         thread_result()?;
 
@@ -379,13 +736,13 @@ impl PulseAudioClient {
     }
 
     fn server_info_callback(server_info: &ServerInfo) {
-        match server_info.default_sink_name.clone() {
-            None => {},
-            Some(default_sink) => {
-                *PULSEAUDIO_DEFAULT_SINK.lock().unwrap() = default_sink.into();
-                PulseAudioClient::send_update_event();
-            }
+        if let Some(default_sink) = server_info.default_sink_name.clone() {
+            *PULSEAUDIO_DEFAULT_SINK.lock().unwrap() = default_sink.into();
+        }
+        if let Some(default_source) = server_info.default_source_name.clone() {
+            *PULSEAUDIO_DEFAULT_SOURCE.lock().unwrap() = default_source.into();
         }
+        PulseAudioClient::send_update_event();
     }
 
     fn sink_info_callback(result: ListResult<&SinkInfo>) {
@@ -396,7 +753,12 @@ impl PulseAudioClient {
                 match sink_info.name.clone() {
                     None => {},
                     Some(name) => {
-                        let info = PulseAudioSinkInfo {
+                        let port = sink_info.active_port
+                            .as_ref()
+                            .and_then(|port| port.description.clone());
+                        let info = PulseAudioDeviceInfo {
+                            index: sink_info.index,
+                            description: port.or_else(|| sink_info.description.clone()).map(|description| description.into_owned()),
                             volume: sink_info.volume,
                             mute: sink_info.mute,
                         };
@@ -408,15 +770,74 @@ impl PulseAudioClient {
         }
     }
 
-    fn subscribe_callback(facility: Option<Facility>, _operation: Option<SubscribeOperation>, index: u32) {
+    fn source_info_callback(result: ListResult<&SourceInfo>) {
+        if let ListResult::Item(source_info) = result {
+            if let Some(name) = source_info.name.clone() {
+                let port = source_info.active_port
+                    .as_ref()
+                    .and_then(|port| port.description.clone());
+                let info = PulseAudioDeviceInfo {
+                    index: source_info.index,
+                    description: port.or_else(|| source_info.description.clone()).map(|description| description.into_owned()),
+                    volume: source_info.volume,
+                    mute: source_info.mute,
+                };
+                PULSEAUDIO_SOURCES.lock().unwrap().insert(name.into(), info);
+                PulseAudioClient::send_update_event();
+            }
+        }
+    }
+
+    fn sink_input_info_callback(result: ListResult<&SinkInputInfo>) {
+        if let ListResult::Item(sink_input_info) = result {
+            // Applications name themselves in the properties, the stream name is a fallback
+            let name = sink_input_info.proplist
+                .gets(properties::APPLICATION_NAME)
+                .or_else(|| sink_input_info.name.clone().map(|name| name.into_owned()))
+                .unwrap_or_else(|| format!("#{}", sink_input_info.index));
+            let info = PulseAudioSinkInputInfo {
+                name,
+                mute: sink_input_info.mute,
+                corked: sink_input_info.corked,
+            };
+            PULSEAUDIO_SINK_INPUTS.lock().unwrap().insert(sink_input_info.index, info);
+            PulseAudioClient::send_update_event();
+        }
+    }
+
+    fn subscribe_callback(facility: Option<Facility>, operation: Option<SubscribeOperation>, index: u32) {
         match facility {
             None => { },
             Some(facility) => match facility {
                 Facility::Server => {
                     let _ = PulseAudioClient::send(PulseAudioClientRequest::GetDefaultDevice);
                 },
-                Facility::Sink => {
-                    let _ = PulseAudioClient::send(PulseAudioClientRequest::GetSinkInfoByIndex(index));
+                Facility::Sink => match operation {
+                    Some(SubscribeOperation::Removed) => {
+                        PULSEAUDIO_SINKS.lock().unwrap().retain(|_, info| info.index != index);
+                        PulseAudioClient::send_update_event();
+                    },
+                    _ => {
+                        let _ = PulseAudioClient::send(PulseAudioClientRequest::GetSinkInfoByIndex(index));
+                    },
+                },
+                Facility::Source => match operation {
+                    Some(SubscribeOperation::Removed) => {
+                        PULSEAUDIO_SOURCES.lock().unwrap().retain(|_, info| info.index != index);
+                        PulseAudioClient::send_update_event();
+                    },
+                    _ => {
+                        let _ = PulseAudioClient::send(PulseAudioClientRequest::GetSourceInfoByIndex(index));
+                    },
+                },
+                Facility::SinkInput => match operation {
+                    Some(SubscribeOperation::Removed) => {
+                        PULSEAUDIO_SINK_INPUTS.lock().unwrap().remove(&index);
+                        PulseAudioClient::send_update_event();
+                    },
+                    _ => {
+                        let _ = PulseAudioClient::send(PulseAudioClientRequest::GetSinkInputInfo(index));
+                    },
                 },
                 _ => { }
             }
@@ -435,34 +856,52 @@ impl PulseAudioClient {
 
 #[cfg(feature = "pulseaudio")]
 impl PulseAudioSoundDevice {
-    fn new() -> Result<Self> {
+    fn new(kind: DeviceKind) -> Result<Self> {
         PulseAudioClient::send(PulseAudioClientRequest::GetDefaultDevice)?;
+        // All sinks are needed to switch between them
+        PulseAudioClient::send(PulseAudioClientRequest::GetSinkInfoList)?;
 
         let device = PulseAudioSoundDevice {
+            kind,
             name: None,
             volume: None,
             volume_avg: 0,
             muted: false,
         };
 
-        PulseAudioClient::send(PulseAudioClientRequest::GetSinkInfoByName(device.name()))?;
+        device.request_info()?;
+        PulseAudioClient::send(PulseAudioClientRequest::GetSinkInputInfoList)?;
 
         Ok(device)
     }
 
-    fn with_name(name: String) -> Result<Self> {
-        PulseAudioClient::send(PulseAudioClientRequest::GetSinkInfoByName(name.clone()))?;
-
-        Ok(PulseAudioSoundDevice {
+    fn with_name(kind: DeviceKind, name: String) -> Result<Self> {
+        let device = PulseAudioSoundDevice {
+            kind,
             name: Some(name),
             volume: None,
             volume_avg: 0,
             muted: false,
-        })
+        };
+
+        device.request_info()?;
+        PulseAudioClient::send(PulseAudioClientRequest::GetSinkInputInfoList)?;
+
+        Ok(device)
     }
 
     fn name(&self) -> String {
-        self.name.clone().unwrap_or_else(|| PULSEAUDIO_DEFAULT_SINK.lock().unwrap().clone())
+        self.name.clone().unwrap_or_else(|| match self.kind {
+            DeviceKind::Sink => PULSEAUDIO_DEFAULT_SINK.lock().unwrap().clone(),
+            DeviceKind::Source => PULSEAUDIO_DEFAULT_SOURCE.lock().unwrap().clone(),
+        })
+    }
+
+    fn request_info(&self) -> Result<()> {
+        PulseAudioClient::send(match self.kind {
+            DeviceKind::Sink => PulseAudioClientRequest::GetSinkInfoByName(self.name()),
+            DeviceKind::Source => PulseAudioClientRequest::GetSourceInfoByName(self.name()),
+        })
     }
 
     fn volume(&mut self, volume: ChannelVolumes) {
@@ -476,12 +915,22 @@ impl SoundDevice for PulseAudioSoundDevice {
     fn volume(&self) -> u32 { self.volume_avg }
     fn muted(&self) -> bool { self.muted }
 
+    fn db(&self) -> Option<f64> {
+        // PulseAudio's software volume is cubic, i.e. 60 dB per decade of its value
+        self.volume
+            .map(|volume| 60.0 * (f64::from(volume.avg().0) / f64::from(VOLUME_NORM.0)).log10())
+    }
+
     fn get_info(&mut self) -> Result<()> {
-        match PULSEAUDIO_SINKS.lock().unwrap().get(&self.name()) {
+        let devices = match self.kind {
+            DeviceKind::Sink => PULSEAUDIO_SINKS.lock().unwrap(),
+            DeviceKind::Source => PULSEAUDIO_SOURCES.lock().unwrap(),
+        };
+        match devices.get(&self.name()) {
             None => {},
-            Some(sink_info) => {
-                self.volume(sink_info.volume);
-                self.muted = sink_info.mute;
+            Some(device_info) => {
+                self.volume(device_info.volume);
+                self.muted = device_info.mute;
             }
         }
 
@@ -502,14 +951,20 @@ impl SoundDevice for PulseAudioSoundDevice {
 
         // update volumes
         self.volume(volume);
-        PulseAudioClient::send(PulseAudioClientRequest::SetSinkVolumeByName(self.name(), volume))?;
+        PulseAudioClient::send(match self.kind {
+            DeviceKind::Sink => PulseAudioClientRequest::SetSinkVolumeByName(self.name(), volume),
+            DeviceKind::Source => PulseAudioClientRequest::SetSourceVolumeByName(self.name(), volume),
+        })?;
 
         Ok(())
     }
 
     fn toggle(&mut self) -> Result<()> {
         self.muted = !self.muted;
-        PulseAudioClient::send(PulseAudioClientRequest::SetSinkMuteByName(self.name(), self.muted))?;
+        PulseAudioClient::send(match self.kind {
+            DeviceKind::Sink => PulseAudioClientRequest::SetSinkMuteByName(self.name(), self.muted),
+            DeviceKind::Source => PulseAudioClientRequest::SetSourceMuteByName(self.name(), self.muted),
+        })?;
 
         Ok(())
     }
@@ -518,16 +973,92 @@ impl SoundDevice for PulseAudioSoundDevice {
         PULSEAUDIO_EVENT_LISTENER.lock().unwrap().insert(id, tx_update_request);
         Ok(())
     }
+
+    fn streams(&self) -> Vec<Stream> {
+        PULSEAUDIO_SINK_INPUTS
+            .lock()
+            .unwrap()
+            .iter()
+            // Paused streams make no sound
+            .filter(|&(_, info)| !info.corked)
+            .map(|(&index, info)| Stream {
+                index,
+                name: info.name.clone(),
+                muted: info.mute,
+            })
+            .collect()
+    }
+
+    fn toggle_stream(&mut self, index: u32) -> Result<()> {
+        let muted = match PULSEAUDIO_SINK_INPUTS.lock().unwrap().get(&index) {
+            Some(info) => info.mute,
+            None => return Ok(()),
+        };
+        PulseAudioClient::send(PulseAudioClientRequest::SetSinkInputMute(index, !muted))
+    }
+
+    fn output_name(&self) -> (String, Option<String>) {
+        let name = self.name();
+        let devices = match self.kind {
+            DeviceKind::Sink => PULSEAUDIO_SINKS.lock().unwrap(),
+            DeviceKind::Source => PULSEAUDIO_SOURCES.lock().unwrap(),
+        };
+        let description = devices.get(&name).and_then(|info| info.description.clone());
+        (name, description)
+    }
+
+    fn next_output(&mut self) -> Result<()> {
+        // A configured device is never switched away from
+        if self.kind != DeviceKind::Sink || self.name.is_some() {
+            return Ok(());
+        }
+
+        let mut sinks: Vec<(u32, String)> = PULSEAUDIO_SINKS
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, info)| (info.index, name.clone()))
+            .collect();
+        sinks.sort();
+        let current = self.name();
+        let next = match sinks.iter().position(|&(_, ref name)| *name == current) {
+            Some(position) => sinks.get(position + 1).or_else(|| sinks.first()),
+            None => sinks.first(),
+        };
+        let next = match next {
+            Some(&(_, ref name)) if *name != current => name.clone(),
+            _ => return Ok(()),
+        };
+
+        PulseAudioClient::send(PulseAudioClientRequest::SetDefaultSink(next.clone()))?;
+        // Streams stay on the sink they play on unless they are moved
+        let streams: Vec<u32> = PULSEAUDIO_SINK_INPUTS.lock().unwrap().keys().cloned().collect();
+        for index in streams {
+            PulseAudioClient::send(PulseAudioClientRequest::MoveSinkInput(index, next.clone()))?;
+        }
+        Ok(())
+    }
 }
 
-// TODO: Use the alsa control bindings to implement push updates
 pub struct Sound {
     text: ButtonWidget,
     id: String,
     device: Box<SoundDevice>,
+    device_kind: DeviceKind,
     step_width: u32,
     config: Config,
     on_click: Option<String>,
+    icon_ramp: IconRamp,
+    show_streams: bool,
+    /// A button per application playing sound, and the stream it controls.
+    streams: Vec<(u32, ButtonWidget)>,
+    format: FormatTemplate,
+    output_names: HashMap<String, String>,
+    output_icons: HashMap<String, String>,
+    output_switch_button: Option<MouseButton>,
+    max_vol: Option<u32>,
+    volume_curve: VolumeCurve,
+    show_volume_when_muted: bool,
 }
 
 #[derive(Deserialize, Debug, Default, Clone)]
@@ -541,12 +1072,64 @@ pub struct SoundConfig {
     #[serde(default = "SoundConfig::default_name")]
     pub name: Option<String>,
 
+    /// ALSA card whose mixer is used
+    #[serde(default = "SoundConfig::default_card")]
+    pub card: String,
+
+    /// Index of the ALSA mixer control, for cards with several controls of the same name
+    #[serde(default = "SoundConfig::default_mixer_index")]
+    pub mixer_index: u32,
+
+    /// How ALSA volumes are turned into percentages
+    #[serde(default = "VolumeScale::default")]
+    pub volume_scale: VolumeScale,
+
     /// The steps volume is in/decreased for the selected audio device (When greater than 50 it gets limited to 50)
     #[serde(default = "SoundConfig::default_step_width")]
     pub step_width: u32,
 
+    /// The highest volume, in percent, scrolling may set
+    #[serde(default = "SoundConfig::default_max_vol")]
+    pub max_vol: Option<u32>,
+
+    /// How scrolling steps through volumes
+    #[serde(default = "VolumeCurve::default")]
+    pub volume_curve: VolumeCurve,
+
+    /// Keep showing the volume while the device is muted
+    #[serde(default = "SoundConfig::default_show_volume_when_muted")]
+    pub show_volume_when_muted: bool,
+
     #[serde(default = "SoundConfig::default_on_click")]
     pub on_click: Option<String>,
+
+    /// Icons shown from the lowest to the highest volume.
+    #[serde(default = "SoundConfig::default_icon_ramp")]
+    pub icon_ramp: Option<IconRamp>,
+
+    /// Whether to control an output (sink) or an input (source) like a microphone
+    #[serde(default = "DeviceKind::default")]
+    pub device_kind: DeviceKind,
+
+    /// Show a button for each application playing sound
+    #[serde(default = "SoundConfig::default_show_streams")]
+    pub show_streams: bool,
+
+    /// Format override
+    #[serde(default = "SoundConfig::default_format")]
+    pub format: String,
+
+    /// Names shown for `{output_name}`, by device name
+    #[serde(default = "SoundConfig::default_output_names")]
+    pub output_names: HashMap<String, String>,
+
+    /// Icons shown instead of the volume icons, by device name
+    #[serde(default = "SoundConfig::default_output_icons")]
+    pub output_icons: HashMap<String, String>,
+
+    /// The mouse button (`left`, `middle` or `right`) switching to the next output
+    #[serde(default = "SoundConfig::default_output_switch_button")]
+    pub output_switch_button: Option<String>,
 }
 
 #[derive(Deserialize, Copy, Clone, Debug)]
@@ -564,47 +1147,244 @@ impl Default for SoundDriver {
     }
 }
 
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeCurve {
+    /// Every step changes the volume by the same amount
+    Linear,
+    /// Steps are smaller at low volumes, like in alsamixer
+    Cubic,
+    /// Every step changes the volume by the same amount of dB
+    Logarithmic,
+}
+
+impl Default for VolumeCurve {
+    fn default() -> Self {
+        VolumeCurve::Linear
+    }
+}
+
+/// dB covered by the logarithmic curve, from silence to 100%
+const LOGARITHMIC_RANGE: f64 = 60.0;
+
+impl VolumeCurve {
+    /// Where a volume in percent lies on the curve, with 100% at 1.
+    fn position(self, volume: u32) -> f64 {
+        let volume = f64::from(volume) / 100.0;
+        match self {
+            VolumeCurve::Linear => volume,
+            VolumeCurve::Cubic => volume.cbrt(),
+            VolumeCurve::Logarithmic if volume > 0.0 => {
+                (1.0 + 20.0 * volume.log10() / LOGARITHMIC_RANGE).max(0.0)
+            }
+            VolumeCurve::Logarithmic => 0.0,
+        }
+    }
+
+    /// The volume in percent at a position on the curve.
+    fn volume(self, position: f64) -> u32 {
+        let position = position.max(0.0);
+        let volume = match self {
+            VolumeCurve::Linear => position,
+            VolumeCurve::Cubic => position.powi(3),
+            VolumeCurve::Logarithmic if position > 0.0 => {
+                10f64.powf((position - 1.0) * LOGARITHMIC_RANGE / 20.0)
+            }
+            VolumeCurve::Logarithmic => 0.0,
+        };
+        (volume * 100.0).round() as u32
+    }
+
+    /// The volume after moving `step` percent along the curve, changing it
+    /// by at least one percent so that rounding can't swallow steps.
+    fn step(self, volume: u32, step: i32) -> u32 {
+        let stepped = self.volume(self.position(volume) + f64::from(step) / 100.0);
+        if step > 0 {
+            max(stepped, volume + 1)
+        } else if step < 0 {
+            min(stepped, volume.saturating_sub(1))
+        } else {
+            volume
+        }
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug)]
+#[serde(rename_all = "lowercase")]
+pub enum VolumeScale {
+    /// Percent of the range of raw values of the control
+    Linear,
+    /// Percent of the range of dB values of the control
+    #[cfg(feature = "native-alsa")]
+    Db,
+}
+
+impl Default for VolumeScale {
+    fn default() -> Self {
+        VolumeScale::Linear
+    }
+}
+
+#[derive(Deserialize, Copy, Clone, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DeviceKind {
+    Sink,
+    Source,
+}
+
+impl Default for DeviceKind {
+    fn default() -> Self {
+        DeviceKind::Sink
+    }
+}
+
+impl DeviceKind {
+    /// The prefix of the names of the icons of devices of this kind.
+    fn icon_prefix(self) -> &'static str {
+        match self {
+            DeviceKind::Sink => "volume",
+            DeviceKind::Source => "microphone",
+        }
+    }
+
+    /// The ALSA simple mixer control used unless configured otherwise.
+    fn default_alsa_name(self) -> &'static str {
+        match self {
+            DeviceKind::Sink => "Master",
+            DeviceKind::Source => "Capture",
+        }
+    }
+}
+
 impl SoundConfig {
     fn default_name() -> Option<String> {
         None
     }
 
+    fn default_card() -> String {
+        "default".to_owned()
+    }
+
+    fn default_mixer_index() -> u32 {
+        0
+    }
+
     fn default_step_width() -> u32 {
         5
     }
 
+    fn default_max_vol() -> Option<u32> {
+        None
+    }
+
+    fn default_show_volume_when_muted() -> bool {
+        false
+    }
+
     fn default_on_click() -> Option<String> {
         None
     }
+
+    fn default_icon_ramp() -> Option<IconRamp> {
+        None
+    }
+
+    fn default_show_streams() -> bool {
+        false
+    }
+
+    fn default_format() -> String {
+        "{volume}%".to_owned()
+    }
+
+    fn default_output_names() -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    fn default_output_icons() -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    fn default_output_switch_button() -> Option<String> {
+        None
+    }
 }
 
 impl Sound {
     fn display(&mut self) -> Result<()> {
         self.device.get_info()?;
 
-        if self.device.muted() {
-            self.text.set_icon("volume_empty");
+        let icon_prefix = self.device_kind.icon_prefix();
+        if self.device.muted() && !self.show_volume_when_muted {
+            self.text.set_icon(&format!("{}_empty", icon_prefix));
             self.text.set_text(
                 self.config
                     .icons
-                    .get("volume_muted")
+                    .get(&format!("{}_muted", icon_prefix))
                     .block_error("sound", "cannot find icon")?
                     .to_owned(),
             );
             self.text.set_state(State::Warning);
         } else {
             let volume = self.device.volume();
-            self.text.set_icon(match volume {
-                0...20 => "volume_empty",
-                21...70 => "volume_half",
-                _ => "volume_full",
-            });
-            self.text.set_text(format!("{:02}%", volume));
-            self.text.set_state(State::Idle);
+            let (name, description) = self.device.output_name();
+            let output_name = self.output_names
+                .get(&name)
+                .cloned()
+                .or(description)
+                .unwrap_or_else(|| name.clone());
+            let muted = self.device.muted();
+            match self.output_icons.get(&name) {
+                Some(icon) => self.text.set_icon(icon),
+                None if muted => self.text.set_icon(&format!("{}_muted", icon_prefix)),
+                None => self.text.set_icon(self.icon_ramp.icon(u64::from(volume))),
+            }
+            let db = self.device
+                .db()
+                .map(|db| format!("{:.1}", db))
+                .unwrap_or_default();
+            let values = map!("{volume}" => format!("{:02}", volume),
+                              "{output_name}" => output_name,
+                              "{db}" => db);
+            self.text.set_text(self.format.render_static_str(&values)?);
+            self.text.set_state(if muted { State::Warning } else { State::Idle });
+        }
+
+        if self.show_streams {
+            let id = &self.id;
+            let config = &self.config;
+            self.streams = self.device
+                .streams()
+                .into_iter()
+                .map(|stream| {
+                    let button = ButtonWidget::new(config.clone(), &format!("{}-stream-{}", id, stream.index))
+                        .with_text(&stream.name)
+                        .with_state(if stream.muted { State::Warning } else { State::Idle });
+                    (stream.index, button)
+                })
+                .collect();
         }
 
         Ok(())
     }
+
+    /// Steps the volume along the configured curve, up to `max_vol`.
+    fn step_volume(&mut self, step: i32) -> Result<()> {
+        let volume = self.device.volume();
+        let mut target = self.volume_curve.step(volume, step);
+        if let Some(max_vol) = self.max_vol {
+            // A volume already above the limit may go down, but not up
+            if step > 0 {
+                target = min(target, max(volume, max_vol));
+            }
+        }
+
+        let delta = target as i32 - volume as i32;
+        if delta != 0 {
+            self.device.set_volume(delta)?;
+        }
+        Ok(())
+    }
 }
 
 impl ConfigBlock for Sound {
@@ -620,13 +1400,15 @@ impl ConfigBlock for Sound {
         #[cfg(not(feature = "pulseaudio"))]
         type PulseAudioSoundDevice =  AlsaSoundDevice;
 
+        let device_kind = block_config.device_kind;
+
         // try to create a pulseaudio device if feature is enabled and `driver != "alsa"`
         let pulseaudio_device: Result<PulseAudioSoundDevice> = match block_config.driver {
             #[cfg(feature = "pulseaudio")]
             SoundDriver::Auto | SoundDriver::PulseAudio =>
                 match block_config.name.clone() {
-                    None => PulseAudioSoundDevice::new(),
-                    Some(name) => PulseAudioSoundDevice::with_name(name)
+                    None => PulseAudioSoundDevice::new(device_kind),
+                    Some(name) => PulseAudioSoundDevice::with_name(device_kind, name)
                 },
             _ => Err(BlockError(
                 "sound".into(),
@@ -637,16 +1419,62 @@ impl ConfigBlock for Sound {
         // prefere PulseAudio if available and selected, fallback to ALSA
         let device: Box<SoundDevice> = match pulseaudio_device {
             Ok(dev) => Box::new(dev),
-            Err(_) => Box::new(AlsaSoundDevice::new(block_config.name.unwrap_or_else(|| "Master".into()))?)
+            Err(_) => {
+                let name = block_config.name.unwrap_or_else(|| device_kind.default_alsa_name().into());
+                #[cfg(feature = "native-alsa")]
+                let alsa_device = AlsaMixerSoundDevice::new(
+                    name,
+                    block_config.card,
+                    block_config.mixer_index,
+                    device_kind,
+                    block_config.volume_scale,
+                )?;
+                #[cfg(not(feature = "native-alsa"))]
+                let alsa_device = AlsaSoundDevice::new(name, block_config.card, block_config.mixer_index)?;
+                Box::new(alsa_device)
+            }
+        };
+
+        let icon_prefix = device_kind.icon_prefix();
+        let icon_ramp = block_config.icon_ramp.unwrap_or_else(|| {
+            IconRamp::new(&[
+                &format!("{}_empty", icon_prefix),
+                &format!("{}_half", icon_prefix),
+                &format!("{}_full", icon_prefix),
+            ]).with_breakpoints(&[21, 71])
+        });
+        icon_ramp.validate("sound")?;
+
+        let output_switch_button = match block_config.output_switch_button.as_ref().map(|button| button.as_str()) {
+            None => None,
+            Some("left") => Some(MouseButton::Left),
+            Some("middle") => Some(MouseButton::Middle),
+            Some("right") => Some(MouseButton::Right),
+            Some(button) => return Err(BlockError(
+                "sound".into(),
+                format!("unknown output_switch_button '{}', expected left, middle or right", button),
+            )),
         };
 
         let mut sound = Self {
-            text: ButtonWidget::new(config.clone(), &id).with_icon("volume_empty"),
+            text: ButtonWidget::new(config.clone(), &id).with_icon(&format!("{}_empty", icon_prefix)),
             id: id.clone(),
             device,
+            device_kind,
             step_width,
             config,
             on_click: block_config.on_click,
+            icon_ramp,
+            show_streams: block_config.show_streams,
+            streams: Vec::new(),
+            format: FormatTemplate::from_string(&block_config.format)
+                .block_error("sound", "invalid format specified for sound")?,
+            output_names: block_config.output_names,
+            output_icons: block_config.output_icons,
+            output_switch_button,
+            max_vol: block_config.max_vol,
+            volume_curve: block_config.volume_curve,
+            show_volume_when_muted: block_config.show_volume_when_muted,
         };
 
         sound.device.monitor(id.clone(), tx_update_request.clone())?;
@@ -665,29 +1493,50 @@ impl Block for Sound {
     }
 
     fn view(&self) -> Vec<&I3BarWidget> {
-        vec![&self.text]
+        let mut widgets: Vec<&I3BarWidget> = vec![&self.text];
+        widgets.extend(self.streams.iter().map(|&(_, ref button)| button as &I3BarWidget));
+        widgets
     }
 
     fn click(&mut self, e: &I3BarEvent) -> Result<()> {
         if let Some(ref name) = e.name {
+            let stream = self.streams
+                .iter()
+                .find(|&&(index, _)| *name == format!("{}-stream-{}", self.id, index))
+                .map(|&(index, _)| index);
+            if let Some(index) = stream {
+                match e.button {
+                    MouseButton::Left | MouseButton::Right => self.device.toggle_stream(index)?,
+                    _ => (),
+                }
+                return Ok(());
+            }
+
             if name.as_str() == self.id {
+                // Switching outputs takes precedence over what the button does otherwise
+                if self.output_switch_button.as_ref() == Some(&e.button) {
+                    self.device.next_output()?;
+                    return Ok(());
+                }
                 match e.button {
                     MouseButton::Right => self.device.toggle()?,
                     MouseButton::Left => match self.on_click {
                         Some(ref cmd) => {
                             let command_broken: Vec<&str> = cmd.split_whitespace().collect();
                             let mut itr = command_broken.iter();
-                            let mut _cmd = Command::new(OsStr::new(&itr.next().unwrap()))
+                            let mut _cmd = stats::command(OsStr::new(&itr.next().unwrap()))
                                 .args(itr)
                                 .spawn();
                         }
                         _ => ()
                     }
                     MouseButton::WheelUp => {
-                        self.device.set_volume(self.step_width as i32)?;
+                        let step = self.step_width as i32;
+                        self.step_volume(step)?;
                     }
                     MouseButton::WheelDown => {
-                        self.device.set_volume(-(self.step_width as i32))?;
+                        let step = -(self.step_width as i32);
+                        self.step_volume(step)?;
                     }
                     _ => ()
                 }
@@ -702,3 +1551,108 @@ impl Block for Sound {
         &self.id
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A device that only keeps its volume.
+    struct FakeDevice {
+        volume: u32,
+    }
+
+    impl SoundDevice for FakeDevice {
+        fn volume(&self) -> u32 {
+            self.volume
+        }
+
+        fn muted(&self) -> bool {
+            false
+        }
+
+        fn get_info(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn set_volume(&mut self, step: i32) -> Result<()> {
+            self.volume = max(self.volume as i32 + step, 0) as u32;
+            Ok(())
+        }
+
+        fn toggle(&mut self) -> Result<()> {
+            Ok(())
+        }
+
+        fn monitor(&mut self, _id: String, _tx_update_request: Sender<Task>) -> Result<()> {
+            Ok(())
+        }
+
+        fn output_name(&self) -> (String, Option<String>) {
+            ("fake".to_owned(), None)
+        }
+    }
+
+    fn sound(volume: u32, max_vol: Option<u32>) -> Sound {
+        Sound {
+            text: ButtonWidget::new(Config::default(), "sound"),
+            id: "sound".to_owned(),
+            device: Box::new(FakeDevice { volume }),
+            device_kind: DeviceKind::Sink,
+            step_width: 5,
+            config: Config::default(),
+            on_click: None,
+            icon_ramp: IconRamp::default(),
+            show_streams: false,
+            streams: Vec::new(),
+            format: FormatTemplate::from_string("{volume}%").unwrap(),
+            output_names: HashMap::new(),
+            output_icons: HashMap::new(),
+            output_switch_button: None,
+            max_vol,
+            volume_curve: VolumeCurve::Linear,
+            show_volume_when_muted: false,
+        }
+    }
+
+    #[test]
+    fn logarithmic_curve_bottoms_out_at_zero() {
+        let curve = VolumeCurve::Logarithmic;
+        assert_eq!(curve.position(0), 0.0);
+        assert_eq!(curve.position(100), 1.0);
+        assert_eq!(curve.volume(0.0), 0);
+        assert_eq!(curve.volume(-1.0), 0);
+        assert_eq!(curve.volume(1.0), 100);
+        assert_eq!(curve.step(0, -5), 0);
+    }
+
+    #[test]
+    fn steps_change_the_volume_by_at_least_one_percent() {
+        assert_eq!(VolumeCurve::Linear.step(50, 5), 55);
+        assert_eq!(VolumeCurve::Linear.step(50, -5), 45);
+        assert_eq!(VolumeCurve::Linear.step(50, 0), 50);
+        // Each of these rounds back to where it started
+        assert_eq!(VolumeCurve::Logarithmic.step(0, 5), 1);
+        assert_eq!(VolumeCurve::Logarithmic.step(1, -5), 0);
+        assert_eq!(VolumeCurve::Cubic.step(1, 1), 2);
+        assert_eq!(VolumeCurve::Cubic.step(2, -1), 1);
+    }
+
+    #[test]
+    fn max_vol_stops_raising_but_not_lowering() {
+        let mut below = sound(58, Some(60));
+        below.step_volume(5).unwrap();
+        assert_eq!(below.device.volume(), 60);
+        below.step_volume(5).unwrap();
+        assert_eq!(below.device.volume(), 60);
+
+        let mut above = sound(80, Some(60));
+        above.step_volume(5).unwrap();
+        assert_eq!(above.device.volume(), 80);
+        above.step_volume(-5).unwrap();
+        assert_eq!(above.device.volume(), 75);
+
+        let mut uncapped = sound(98, None);
+        uncapped.step_volume(5).unwrap();
+        assert_eq!(uncapped.device.volume(), 103);
+    }
+}
@@ -1,6 +1,5 @@
 use std::time::{Duration, Instant};
-use std::process::Command;
-use std::thread::spawn;
+use stats;
 use std::sync::{Arc, Mutex};
 use chan::{async, Receiver, Sender};
 use scheduler::Task;
@@ -9,6 +8,7 @@ use block::{Block, ConfigBlock};
 use config::Config;
 use de::deserialize_duration;
 use errors::*;
+use logging::spawn_thread;
 use widgets::button::ButtonWidget;
 use widget::{I3BarWidget, State};
 use input::{I3BarEvent, MouseButton};
@@ -45,9 +45,9 @@ impl SpeedTestConfig {
     }
 }
 
-fn get_values(bytes: bool) -> Result<String> {
-    let mut cmd = Command::new("speedtest-cli");
-    cmd.arg("--simple");
+fn get_values(bytes: bool, id: &str) -> Result<String> {
+    let mut cmd = stats::command("speedtest-cli");
+    cmd.block(id).arg("--simple");
     if bytes {
         cmd.arg("--bytes");
     }
@@ -73,27 +73,44 @@ fn parse_values(output: &str) -> Result<Vec<f32>> {
     Ok(vals)
 }
 
+/// Runs speedtest-cli once, returning ping, download and upload speed.
+fn measure(bytes: bool, id: &str) -> Result<Vec<f32>> {
+    let vals = parse_values(&get_values(bytes, id)?)?;
+    if vals.len() != 3 {
+        return Err(BlockError(
+            "speedtest".to_owned(),
+            format!("expected 3 values from speedtest-cli, got {}", vals.len()),
+        ));
+    }
+    Ok(vals)
+}
+
 fn make_thread(recv: Receiver<()>, done: Sender<Task>, values: Arc<Mutex<(bool, Vec<f32>)>>, config: SpeedTestConfig, id: String) {
-    spawn(move || loop {
-        if recv.recv().is_some() {
-            if let Ok(output) = get_values(config.bytes) {
-                if let Ok(vals) = parse_values(&output) {
-                    if vals.len() == 3 {
-                        let (ref mut update, ref mut values) = *values
-                            .lock()
-                            .expect("main thread paniced while holding speedtest-values mutex");
-                        *values = vals;
-
-                        *update = true;
-
-                        done.send(Task {
-                            id: id.clone(),
-                            update_time: Instant::now(),
-                        })
-                    }
+    spawn_thread("speedtest", move || {
+        while recv.recv().is_some() {
+            // A failed measurement keeps the last values until the next one
+            let vals = match measure(config.bytes, &id) {
+                Ok(vals) => vals,
+                Err(error) => {
+                    warn!("speedtest", "speed test failed: {:?}", error);
+                    continue;
                 }
+            };
+
+            {
+                let mut shared = values
+                    .lock()
+                    .block_error("speedtest", "speedtest values mutex poisoned")?;
+                shared.1 = vals;
+                shared.0 = true;
             }
+
+            done.send(Task {
+                id: id.clone(),
+                update_time: Instant::now(),
+            })
         }
+        Ok(())
     });
 }
 
@@ -1,11 +1,12 @@
 use std::time::{Duration, Instant};
 use chan::Sender;
-use std::thread;
 use std::sync::{Arc, Mutex};
 
 use block::{Block, ConfigBlock};
 use config::Config;
 use errors::*;
+use i3_dispatcher::{self, Event, EventKind, I3Event};
+use listener::DISCONNECTED;
 use widgets::text::TextWidget;
 use widget::{I3BarWidget, State};
 use scheduler::Task;
@@ -13,9 +14,6 @@ use scheduler::Task;
 use uuid::Uuid;
 
 extern crate i3ipc;
-use self::i3ipc::I3EventListener;
-use self::i3ipc::Subscription;
-use self::i3ipc::event::Event;
 use self::i3ipc::event::inner::WindowChange;
 
 use std::collections::HashMap;
@@ -38,7 +36,6 @@ impl ConfigBlock for SplitStatus {
 
     fn new(_block_config: Self::Config, config: Config, tx: Sender<Task>) -> Result<Self> {
         let id = Uuid::new_v4().simple().to_string();
-        let id_clone = id.clone();
 
         let container_mapping_original = Arc::new(Mutex::new(HashMap::new()));
         let container_mapping = container_mapping_original.clone();
@@ -46,91 +43,73 @@ impl ConfigBlock for SplitStatus {
         let current_window_original = Arc::new(Mutex::new(0i64));
         let current_window = current_window_original.clone();
 
-        thread::spawn(move || {
-            // establish connection.
-            let mut listener = I3EventListener::connect().unwrap();
-
-            // subscribe to a couple events.
-            let subs = [Subscription::Binding, Subscription::Window];
-            listener.subscribe(&subs).unwrap();
-
-            // handle them
-            for event in listener.listen() {
-                match event.unwrap() {
-                    Event::BindingEvent(e) => {
-                        let the_command = e.binding.command;
-                        match the_command.as_ref() {
-                            "split h" => {
-                                    let mut container_mapping = container_mapping_original.lock().unwrap();
-                                    let mut current_window = current_window_original.lock().unwrap();
-                                    container_mapping.insert(*current_window, false);
-
-                                    tx.send(Task {
-                                        id: id_clone.clone(),
-                                        update_time: Instant::now(),
-                                    });
-                            },
-                            "split v" => {
-                                    let mut container_mapping = container_mapping_original.lock().unwrap();
-                                    let mut current_window = current_window_original.lock().unwrap();
-                                    container_mapping.insert(*current_window, true);
-
-                                    tx.send(Task {
-                                        id: id_clone.clone(),
-                                        update_time: Instant::now(),
-                                    });
-                            },
-                            _ => {}
-                        }
+        let update_id = id.clone();
+        let update = move || {
+            tx.send(Task {
+                id: update_id.clone(),
+                update_time: Instant::now(),
+            })
+        };
+
+        i3_dispatcher::subscribe(&[EventKind::Binding, EventKind::Window], move |event| match event {
+            I3Event::Event(&Event::BindingEvent(ref e)) => {
+                let the_command = &e.binding.command;
+                match the_command.as_ref() {
+                    "split h" => {
+                            let mut container_mapping = container_mapping_original.lock().unwrap_or_else(|e| e.into_inner());
+                            let mut current_window = current_window_original.lock().unwrap_or_else(|e| e.into_inner());
+                            container_mapping.insert(*current_window, false);
+
+                            update();
+                    },
+                    "split v" => {
+                            let mut container_mapping = container_mapping_original.lock().unwrap_or_else(|e| e.into_inner());
+                            let mut current_window = current_window_original.lock().unwrap_or_else(|e| e.into_inner());
+                            container_mapping.insert(*current_window, true);
+
+                            update();
+                    },
+                    _ => {}
+                }
+            },
+            I3Event::Event(&Event::WindowEvent(ref win_eventinfo)) => {
+                let current_id = win_eventinfo.container.id;
+                let mut container_mapping = container_mapping_original.lock().unwrap_or_else(|e| e.into_inner());
+                let mut container_mapping_clone = container_mapping.clone();
+                //Set the current window (global variable) as the current window ID from
+                //this event.
+                let mut current_window = current_window_original.lock().unwrap_or_else(|e| e.into_inner());
+                match win_eventinfo.change {
+                    WindowChange::New => {
+                        //New windows automatically inherit the current state
+                        //as their parents
+                        let mut parent_state = container_mapping_clone.get(&current_window).unwrap_or(&false);
+                        container_mapping.insert(current_id, *parent_state);
+                        *current_window = current_id;
+                        update();
+                    },
+                    WindowChange::Close => {
+                        *current_window = current_id;
+                        //When we close a window, remove it from the HashMap
+                        //as it is no longer needed.
+                        container_mapping.remove(&current_id);
+                        update();
                     },
-                    Event::WindowEvent(win_eventinfo) => {
-                        let current_id = win_eventinfo.container.id;
-                        let mut container_mapping = container_mapping_original.lock().unwrap();
-                        let mut container_mapping_clone = container_mapping.clone();
-                        //Set the current window (global variable) as the current window ID from
-                        //this event.
-                        let mut current_window = current_window_original.lock().unwrap();
-                        match win_eventinfo.change {
-                            WindowChange::New => {
-                                //New windows automatically inherit the current state
-                                //as their parents
-                                let mut parent_state = container_mapping_clone.get(&current_window).unwrap_or(&false);
-                                container_mapping.insert(current_id, *parent_state);
-                                *current_window = current_id;
-                                tx.send(Task {
-                                    id: id_clone.clone(),
-                                    update_time: Instant::now(),
-                                });
-                            },
-                            WindowChange::Close => {
-                                *current_window = current_id;
-                                //When we close a window, remove it from the HashMap
-                                //as it is no longer needed.
-                                container_mapping.remove(&current_id);
-                                tx.send(Task {
-                                    id: id_clone.clone(),
-                                    update_time: Instant::now(),
-                                });
-                            },
-                            WindowChange::Focus => {
-                                *current_window = current_id;
-                                //There is a chance that we focus on a window which doesn't
-                                //exist in the current HashMap. If so, add it to that 
-                                //HashMap.
-                                if !container_mapping.contains_key(&current_id) {
-                                    container_mapping.insert(current_id, false);
-                                }
-                                tx.send(Task {
-                                    id: id_clone.clone(),
-                                    update_time: Instant::now(),
-                                });
-                            },
-                            _ => {},
+                    WindowChange::Focus => {
+                        *current_window = current_id;
+                        //There is a chance that we focus on a window which doesn't
+                        //exist in the current HashMap. If so, add it to that 
+                        //HashMap.
+                        if !container_mapping.contains_key(&current_id) {
+                            container_mapping.insert(current_id, false);
                         }
+                        update();
                     },
-                    _ => unreachable!(),
+                    _ => {},
                 }
-            }
+            },
+            I3Event::Event(_) => {}
+            I3Event::Connected | I3Event::Disconnected => update(),
         });
 
         Ok(SplitStatus {
@@ -145,7 +124,13 @@ impl ConfigBlock for SplitStatus {
 
 impl Block for SplitStatus {
     fn update(&mut self) -> Result<Option<Duration>> {
-        
+        if !i3_dispatcher::is_connected() {
+            self.text.set_text(DISCONNECTED.to_owned());
+            self.text.set_state(State::Idle);
+            return Ok(None);
+        }
+        self.text.set_text(String::new());
+
         let container = self.container_mapping
             .lock()
             .block_error("Oops", "failed to aquire lock")?;
@@ -1,5 +1,5 @@
 use std::time::Duration;
-use std::process::Command;
+use stats;
 use util::FormatTemplate;
 use chan::Sender;
 use scheduler::Task;
@@ -113,7 +113,7 @@ impl ConfigBlock for Temperature {
 
 impl Block for Temperature {
     fn update(&mut self) -> Result<Option<Duration>> {
-        let output = Command::new("sensors")
+        let output = stats::command("sensors")
             .args(&["-u"])
             .output()
             .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_owned())
@@ -137,7 +137,7 @@ impl Block for Temperature {
                         }
                         Ok(t) => {
                             // This error is recoverable and therefore should not stop the program
-                            eprintln!("Temperature ({}) outside of range ([-100, 150])", t);
+                            warn!("temperature", "temperature ({}) outside of range ([-100, 150])", t);
                             Ok(())
                         }
                         Err(_) => Err(BlockError(
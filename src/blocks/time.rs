@@ -1,5 +1,5 @@
 use std::time::Duration;
-use std::process::Command;
+use stats;
 use std::ffi::OsStr;
 
 use block::{Block, ConfigBlock};
@@ -101,7 +101,7 @@ impl Block for Time {
             if name.as_str() == self.id && self.on_click.is_some() {
                 let command_broken: Vec<&str> = command.split_whitespace().collect();
                 let mut itr = command_broken.iter();
-                let mut _cmd = Command::new(OsStr::new(&itr.next().unwrap()))
+                let mut _cmd = stats::command(OsStr::new(&itr.next().unwrap()))
                     .args(itr)
                     .spawn();
             }
@@ -1,6 +1,6 @@
 use std::env;
 use std::time::Duration;
-use std::process::Command;
+use stats;
 use chan::Sender;
 use scheduler::Task;
 
@@ -57,7 +57,7 @@ pub struct ToggleConfig {
 impl Toggle {
 
     fn execute(&self, cmd: &String) -> bool {
-        Command::new(env::var("SHELL").unwrap_or("sh".to_owned()))
+        stats::command(env::var("SHELL").unwrap_or("sh".to_owned()))
            .args(&["-c", cmd])
            .output()
            .expect("failed to execute toggle command")
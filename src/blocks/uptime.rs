@@ -1,4 +1,3 @@
-use std::path::Path;
 use std::time::Duration;
 
 use chan::Sender;
@@ -19,7 +18,7 @@ pub struct Uptime {
     update_interval: Duration,
 
     //useful, but optional
-    #[allow(dead_code)] config: Config,
+    config: Config,
     #[allow(dead_code)] tx_update_request: Sender<Task>,
 }
 
@@ -53,7 +52,7 @@ impl ConfigBlock for Uptime {
 
 impl Block for Uptime {
     fn update(&mut self) -> Result<Option<Duration>> {
-        let uptime_raw = match read_file("uptime", Path::new("/proc/uptime")) {
+        let uptime_raw = match read_file("uptime", &self.config.roots.procfs_path("uptime")) {
             Ok(file) => file,
             Err(e) => {
                 return Err(BlockError(
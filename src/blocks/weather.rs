@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use std::process::Command;
+use stats;
 use std::time::Duration;
 use chan::Sender;
 use serde_json;
@@ -56,7 +56,7 @@ impl Weather {
                 ref city_id,
                 ref units,
             } => {
-                let output = Command::new("sh")
+                let output = stats::command("sh")
                     .args(
                         &[
                             "-c",
@@ -0,0 +1,242 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use chan::Sender;
+use uuid::Uuid;
+
+use block::{Block, ConfigBlock};
+use config::Config;
+use errors::*;
+use i3_dispatcher::{self, EventKind, I3Event, Node};
+use input::{I3BarEvent, MouseButton};
+use listener::DISCONNECTED;
+use scheduler::Task;
+use widget::{I3BarWidget, State};
+use widgets::button::ButtonWidget;
+
+extern crate i3ipc;
+use self::i3ipc::reply::{NodeType, WindowProperty};
+
+pub struct Workspaces {
+    id: String,
+    buttons: Vec<ButtonWidget>,
+    /// The workspace shown by each button, by button name.
+    workspaces: HashMap<String, String>,
+    disconnected: ButtonWidget,
+    /// Whether the last query of i3 failed, which shows like a lost connection.
+    failed: bool,
+    output: Option<String>,
+    names: HashMap<String, String>,
+    app_icons: HashMap<String, String>,
+    config: Config,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct WorkspacesConfig {
+    /// Only show the workspaces on this output
+    #[serde(default = "WorkspacesConfig::default_output")]
+    pub output: Option<String>,
+
+    /// Text shown instead of the names of workspaces
+    #[serde(default = "WorkspacesConfig::default_names")]
+    pub names: HashMap<String, String>,
+
+    /// Icons shown for the windows on a workspace, by window class
+    #[serde(default = "WorkspacesConfig::default_app_icons")]
+    pub app_icons: HashMap<String, String>,
+}
+
+impl WorkspacesConfig {
+    fn default_output() -> Option<String> {
+        None
+    }
+
+    fn default_names() -> HashMap<String, String> {
+        HashMap::new()
+    }
+
+    fn default_app_icons() -> HashMap<String, String> {
+        HashMap::new()
+    }
+}
+
+impl ConfigBlock for Workspaces {
+    type Config = WorkspacesConfig;
+
+    fn new(block_config: Self::Config, config: Config, tx: Sender<Task>) -> Result<Self> {
+        let id = Uuid::new_v4().simple().to_string();
+
+        // Windows only matter for their icons
+        let mut kinds = vec![EventKind::Workspace, EventKind::Output];
+        if !block_config.app_icons.is_empty() {
+            kinds.push(EventKind::Window);
+        }
+
+        let update_id = id.clone();
+        i3_dispatcher::subscribe(&kinds, move |_: I3Event| {
+            tx.send(Task {
+                id: update_id.clone(),
+                update_time: Instant::now(),
+            })
+        });
+
+        Ok(Workspaces {
+            disconnected: ButtonWidget::new(config.clone(), &id).with_text(DISCONNECTED),
+            failed: false,
+            id,
+            buttons: Vec::new(),
+            workspaces: HashMap::new(),
+            output: block_config.output,
+            names: block_config.names,
+            // Window classes are matched ignoring case
+            app_icons: block_config
+                .app_icons
+                .into_iter()
+                .map(|(class, icon)| (class.to_lowercase(), icon))
+                .collect(),
+            config,
+        })
+    }
+}
+
+/// Collects the classes of all windows in `node`, floating ones included.
+fn window_classes(node: &Node, classes: &mut Vec<String>) {
+    if let Some(class) = node.window_properties
+        .as_ref()
+        .and_then(|properties| properties.get(&WindowProperty::Class))
+    {
+        classes.push(class.to_lowercase());
+    }
+    for child in node.nodes.iter().chain(node.floating_nodes.iter()) {
+        window_classes(child, classes);
+    }
+}
+
+/// Collects the window classes of each workspace in `node`, by workspace name.
+fn workspace_classes(node: &Node, workspaces: &mut HashMap<String, Vec<String>>) {
+    if let NodeType::Workspace = node.nodetype {
+        if let Some(ref name) = node.name {
+            let mut classes = Vec::new();
+            window_classes(node, &mut classes);
+            workspaces.insert(name.clone(), classes);
+        }
+        return;
+    }
+    for child in &node.nodes {
+        workspace_classes(child, workspaces);
+    }
+}
+
+impl Workspaces {
+    /// The icons of the windows on each workspace, each icon once.
+    fn icons(&self) -> Result<HashMap<String, String>> {
+        let mut classes = HashMap::new();
+        if !self.app_icons.is_empty() {
+            workspace_classes(&i3_dispatcher::get_tree()?, &mut classes);
+        }
+
+        Ok(classes
+            .into_iter()
+            .map(|(workspace, classes)| {
+                let mut icons: Vec<&str> = Vec::new();
+                for class in &classes {
+                    if let Some(icon) = self.app_icons.get(class) {
+                        if !icons.contains(&icon.as_str()) {
+                            icons.push(icon);
+                        }
+                    }
+                }
+                (workspace, icons.join(" "))
+            })
+            .collect())
+    }
+
+    /// Queries i3 for the workspaces and makes a button for each.
+    fn query(&mut self) -> Result<()> {
+        let icons = self.icons()?;
+        for workspace in i3_dispatcher::get_workspaces()?.workspaces {
+            if self.output.as_ref().map_or(false, |output| *output != workspace.output) {
+                continue;
+            }
+
+            let mut text = self.names
+                .get(&workspace.name)
+                .cloned()
+                .unwrap_or_else(|| workspace.name.clone());
+            match icons.get(&workspace.name) {
+                Some(icons) if !icons.is_empty() => text = format!("{} {}", text, icons),
+                _ => {}
+            }
+
+            let state = if workspace.urgent {
+                State::Critical
+            } else if workspace.focused {
+                State::Info
+            } else if workspace.visible {
+                State::Good
+            } else {
+                State::Idle
+            };
+
+            let name = format!("{}:{}", self.id, workspace.name);
+            self.buttons.push(ButtonWidget::new(self.config.clone(), &name)
+                .with_text(&text)
+                .with_state(state));
+            self.workspaces.insert(name, workspace.name);
+        }
+        Ok(())
+    }
+}
+
+impl Block for Workspaces {
+    fn update(&mut self) -> Result<Option<Duration>> {
+        self.buttons.clear();
+        self.workspaces.clear();
+        self.failed = false;
+        if !i3_dispatcher::is_connected() {
+            return Ok(None);
+        }
+
+        // i3 may be restarting, the next event updates the block again
+        if let Err(error) = self.query() {
+            warn!("workspaces", "failed to query i3: {:?}", error);
+            self.buttons.clear();
+            self.workspaces.clear();
+            self.failed = true;
+        }
+
+        Ok(None)
+    }
+
+    fn view(&self) -> Vec<&I3BarWidget> {
+        if self.failed || !i3_dispatcher::is_connected() {
+            return vec![&self.disconnected];
+        }
+        self.buttons.iter().map(|button| button as &I3BarWidget).collect()
+    }
+
+    fn click(&mut self, e: &I3BarEvent) -> Result<()> {
+        let workspace = match e.name.as_ref().and_then(|name| self.workspaces.get(name)) {
+            Some(workspace) => workspace,
+            None => return Ok(()),
+        };
+
+        let result = match e.button {
+            MouseButton::Left => {
+                i3_dispatcher::run_command(&format!("workspace \"{}\"", workspace.replace('"', "\\\"")))
+            }
+            MouseButton::WheelUp => i3_dispatcher::run_command("workspace prev_on_output"),
+            MouseButton::WheelDown => i3_dispatcher::run_command("workspace next_on_output"),
+            _ => Ok(()),
+        };
+        if let Err(error) = result {
+            warn!("workspaces", "failed to switch workspaces: {:?}", error);
+        }
+        Ok(())
+    }
+
+    fn id(&self) -> &str {
+        &self.id
+    }
+}
@@ -1,5 +1,5 @@
 use std::time::Duration;
-use std::process::Command;
+use stats;
 use std::str::FromStr;
 use chan::Sender;
 use scheduler::Task;
@@ -32,7 +32,7 @@ impl Monitor {
     }
 
     fn set_brightness(&mut self, step: i32) {
-        Command::new("sh")
+        stats::command("sh")
             .args(&[
                 "-c",
                 format!(
@@ -111,7 +111,7 @@ macro_rules! unwrap_or_continue {
 impl Xrandr {
     fn get_active_monitors() -> Result<Option<Vec<String>>> {
         let active_montiors_cli = String::from_utf8(
-            Command::new("sh")
+            stats::command("sh")
                 .args(&["-c", "xrandr --listactivemonitors | grep \\/"])
                 .output()
                 .block_error("xrandr", "couldn't collect active xrandr monitors")?
@@ -142,7 +142,7 @@ impl Xrandr {
             monitor_names.join(" connected\\|")
         );
         let monitor_info_cli = String::from_utf8(
-            Command::new("sh")
+            stats::command("sh")
                 .args(&["-c", grep_arg.as_str()])
                 .output()
                 .block_error("xrandr", "couldn't collect xrandr monitor info")?
@@ -0,0 +1,332 @@
+//! Color handling for themes and widgets.
+//!
+//! Colors can be given as `#rgb`, `#rrggbb` or `#rrggbbaa` hex codes, as one of the
+//! named CSS colors, or as `auto`, which lets the bar derive the color from a
+//! neighbouring block (used for separators).
+
+use std::cmp::{max, min};
+use std::fmt;
+use std::str::FromStr;
+
+/// A color value as found in themes.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Color {
+    Auto,
+    Rgba(u8, u8, u8, u8),
+}
+
+/// The ways a tint color can be applied to a base color.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TintMode {
+    /// Add both colors channel by channel, saturating at the maximum.
+    Add,
+    /// Mix the tint into the base color, weighted by the alpha of the tint.
+    Blend,
+    /// Take the lighter value of each channel.
+    Lighten,
+    /// Take the darker value of each channel.
+    Darken,
+    /// Multiply both colors channel by channel.
+    Multiply,
+}
+
+impl Default for TintMode {
+    fn default() -> Self {
+        TintMode::Add
+    }
+}
+
+impl FromStr for TintMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "add" => Ok(TintMode::Add),
+            "blend" => Ok(TintMode::Blend),
+            "lighten" => Ok(TintMode::Lighten),
+            "darken" => Ok(TintMode::Darken),
+            "multiply" => Ok(TintMode::Multiply),
+            _ => Err(format!("unknown tint mode '{}'", s)),
+        }
+    }
+}
+
+impl Color {
+    /// Applies `tint` to this color. `auto` colors are left untouched, since
+    /// they are only resolved when the bar is printed.
+    pub fn tint(self, tint: Color, mode: TintMode) -> Color {
+        let ((r_a, g_a, b_a, a_a), (r_b, g_b, b_b, a_b)) = match (self, tint) {
+            (Color::Rgba(r_a, g_a, b_a, a_a), Color::Rgba(r_b, g_b, b_b, a_b)) => ((r_a, g_a, b_a, a_a), (r_b, g_b, b_b, a_b)),
+            _ => return self,
+        };
+
+        match mode {
+            TintMode::Add => Color::Rgba(r_a.saturating_add(r_b), g_a.saturating_add(g_b), b_a.saturating_add(b_b), a_a.saturating_add(a_b)),
+            TintMode::Blend => {
+                let blend = |base: u8, tint: u8| ((u32::from(base) * (255 - u32::from(a_b)) + u32::from(tint) * u32::from(a_b)) / 255) as u8;
+                Color::Rgba(blend(r_a, r_b), blend(g_a, g_b), blend(b_a, b_b), a_a)
+            }
+            TintMode::Lighten => Color::Rgba(max(r_a, r_b), max(g_a, g_b), max(b_a, b_b), a_a),
+            TintMode::Darken => Color::Rgba(min(r_a, r_b), min(g_a, g_b), min(b_a, b_b), a_a),
+            TintMode::Multiply => {
+                let multiply = |base: u8, tint: u8| (u32::from(base) * u32::from(tint) / 255) as u8;
+                Color::Rgba(multiply(r_a, r_b), multiply(g_a, g_b), multiply(b_a, b_b), a_a)
+            }
+        }
+    }
+}
+
+impl FromStr for Color {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        match s {
+            "auto" => return Ok(Color::Auto),
+            "transparent" => return Ok(Color::Rgba(0, 0, 0, 0)),
+            _ => {}
+        }
+
+        if !s.starts_with('#') {
+            return named_color(&s.to_lowercase())
+                .map(|(r, g, b)| Color::Rgba(r, g, b, 0xff))
+                .ok_or_else(|| format!("unknown color '{}'", s));
+        }
+
+        let hex = &s[1..];
+        if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return Err(format!("invalid color code '{}'", s));
+        }
+        // All characters are ASCII from here on, so slicing by byte index is safe.
+        let channel = |i: usize, len: usize| -> u8 {
+            let value = u8::from_str_radix(&hex[i * len..(i + 1) * len], 16).unwrap_or(0);
+            if len == 1 {
+                value * 0x11
+            } else {
+                value
+            }
+        };
+
+        match hex.len() {
+            3 => Ok(Color::Rgba(channel(0, 1), channel(1, 1), channel(2, 1), 0xff)),
+            6 => Ok(Color::Rgba(channel(0, 2), channel(1, 2), channel(2, 2), 0xff)),
+            8 => Ok(Color::Rgba(channel(0, 2), channel(1, 2), channel(2, 2), channel(3, 2))),
+            _ => Err(format!("invalid color code '{}', expected #rgb, #rrggbb or #rrggbbaa", s)),
+        }
+    }
+}
+
+impl fmt::Display for Color {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Color::Auto => write!(f, "auto"),
+            Color::Rgba(r, g, b, 0xff) => write!(f, "#{:02X}{:02X}{:02X}", r, g, b),
+            Color::Rgba(r, g, b, a) => write!(f, "#{:02X}{:02X}{:02X}{:02X}", r, g, b, a),
+        }
+    }
+}
+
+/// Looks up one of the named CSS colors.
+fn named_color(name: &str) -> Option<(u8, u8, u8)> {
+    match name {
+        "aliceblue" => Some((0xf0, 0xf8, 0xff)),
+        "antiquewhite" => Some((0xfa, 0xeb, 0xd7)),
+        "aqua" => Some((0x00, 0xff, 0xff)),
+        "aquamarine" => Some((0x7f, 0xff, 0xd4)),
+        "azure" => Some((0xf0, 0xff, 0xff)),
+        "beige" => Some((0xf5, 0xf5, 0xdc)),
+        "bisque" => Some((0xff, 0xe4, 0xc4)),
+        "black" => Some((0x00, 0x00, 0x00)),
+        "blanchedalmond" => Some((0xff, 0xeb, 0xcd)),
+        "blue" => Some((0x00, 0x00, 0xff)),
+        "blueviolet" => Some((0x8a, 0x2b, 0xe2)),
+        "brown" => Some((0xa5, 0x2a, 0x2a)),
+        "burlywood" => Some((0xde, 0xb8, 0x87)),
+        "cadetblue" => Some((0x5f, 0x9e, 0xa0)),
+        "chartreuse" => Some((0x7f, 0xff, 0x00)),
+        "chocolate" => Some((0xd2, 0x69, 0x1e)),
+        "coral" => Some((0xff, 0x7f, 0x50)),
+        "cornflowerblue" => Some((0x64, 0x95, 0xed)),
+        "cornsilk" => Some((0xff, 0xf8, 0xdc)),
+        "crimson" => Some((0xdc, 0x14, 0x3c)),
+        "cyan" => Some((0x00, 0xff, 0xff)),
+        "darkblue" => Some((0x00, 0x00, 0x8b)),
+        "darkcyan" => Some((0x00, 0x8b, 0x8b)),
+        "darkgoldenrod" => Some((0xb8, 0x86, 0x0b)),
+        "darkgray" => Some((0xa9, 0xa9, 0xa9)),
+        "darkgreen" => Some((0x00, 0x64, 0x00)),
+        "darkgrey" => Some((0xa9, 0xa9, 0xa9)),
+        "darkkhaki" => Some((0xbd, 0xb7, 0x6b)),
+        "darkmagenta" => Some((0x8b, 0x00, 0x8b)),
+        "darkolivegreen" => Some((0x55, 0x6b, 0x2f)),
+        "darkorange" => Some((0xff, 0x8c, 0x00)),
+        "darkorchid" => Some((0x99, 0x32, 0xcc)),
+        "darkred" => Some((0x8b, 0x00, 0x00)),
+        "darksalmon" => Some((0xe9, 0x96, 0x7a)),
+        "darkseagreen" => Some((0x8f, 0xbc, 0x8f)),
+        "darkslateblue" => Some((0x48, 0x3d, 0x8b)),
+        "darkslategray" => Some((0x2f, 0x4f, 0x4f)),
+        "darkslategrey" => Some((0x2f, 0x4f, 0x4f)),
+        "darkturquoise" => Some((0x00, 0xce, 0xd1)),
+        "darkviolet" => Some((0x94, 0x00, 0xd3)),
+        "deeppink" => Some((0xff, 0x14, 0x93)),
+        "deepskyblue" => Some((0x00, 0xbf, 0xff)),
+        "dimgray" => Some((0x69, 0x69, 0x69)),
+        "dimgrey" => Some((0x69, 0x69, 0x69)),
+        "dodgerblue" => Some((0x1e, 0x90, 0xff)),
+        "firebrick" => Some((0xb2, 0x22, 0x22)),
+        "floralwhite" => Some((0xff, 0xfa, 0xf0)),
+        "forestgreen" => Some((0x22, 0x8b, 0x22)),
+        "fuchsia" => Some((0xff, 0x00, 0xff)),
+        "gainsboro" => Some((0xdc, 0xdc, 0xdc)),
+        "ghostwhite" => Some((0xf8, 0xf8, 0xff)),
+        "gold" => Some((0xff, 0xd7, 0x00)),
+        "goldenrod" => Some((0xda, 0xa5, 0x20)),
+        "gray" => Some((0x80, 0x80, 0x80)),
+        "green" => Some((0x00, 0x80, 0x00)),
+        "greenyellow" => Some((0xad, 0xff, 0x2f)),
+        "grey" => Some((0x80, 0x80, 0x80)),
+        "honeydew" => Some((0xf0, 0xff, 0xf0)),
+        "hotpink" => Some((0xff, 0x69, 0xb4)),
+        "indianred" => Some((0xcd, 0x5c, 0x5c)),
+        "indigo" => Some((0x4b, 0x00, 0x82)),
+        "ivory" => Some((0xff, 0xff, 0xf0)),
+        "khaki" => Some((0xf0, 0xe6, 0x8c)),
+        "lavender" => Some((0xe6, 0xe6, 0xfa)),
+        "lavenderblush" => Some((0xff, 0xf0, 0xf5)),
+        "lawngreen" => Some((0x7c, 0xfc, 0x00)),
+        "lemonchiffon" => Some((0xff, 0xfa, 0xcd)),
+        "lightblue" => Some((0xad, 0xd8, 0xe6)),
+        "lightcoral" => Some((0xf0, 0x80, 0x80)),
+        "lightcyan" => Some((0xe0, 0xff, 0xff)),
+        "lightgoldenrodyellow" => Some((0xfa, 0xfa, 0xd2)),
+        "lightgray" => Some((0xd3, 0xd3, 0xd3)),
+        "lightgreen" => Some((0x90, 0xee, 0x90)),
+        "lightgrey" => Some((0xd3, 0xd3, 0xd3)),
+        "lightpink" => Some((0xff, 0xb6, 0xc1)),
+        "lightsalmon" => Some((0xff, 0xa0, 0x7a)),
+        "lightseagreen" => Some((0x20, 0xb2, 0xaa)),
+        "lightskyblue" => Some((0x87, 0xce, 0xfa)),
+        "lightslategray" => Some((0x77, 0x88, 0x99)),
+        "lightslategrey" => Some((0x77, 0x88, 0x99)),
+        "lightsteelblue" => Some((0xb0, 0xc4, 0xde)),
+        "lightyellow" => Some((0xff, 0xff, 0xe0)),
+        "lime" => Some((0x00, 0xff, 0x00)),
+        "limegreen" => Some((0x32, 0xcd, 0x32)),
+        "linen" => Some((0xfa, 0xf0, 0xe6)),
+        "magenta" => Some((0xff, 0x00, 0xff)),
+        "maroon" => Some((0x80, 0x00, 0x00)),
+        "mediumaquamarine" => Some((0x66, 0xcd, 0xaa)),
+        "mediumblue" => Some((0x00, 0x00, 0xcd)),
+        "mediumorchid" => Some((0xba, 0x55, 0xd3)),
+        "mediumpurple" => Some((0x93, 0x70, 0xdb)),
+        "mediumseagreen" => Some((0x3c, 0xb3, 0x71)),
+        "mediumslateblue" => Some((0x7b, 0x68, 0xee)),
+        "mediumspringgreen" => Some((0x00, 0xfa, 0x9a)),
+        "mediumturquoise" => Some((0x48, 0xd1, 0xcc)),
+        "mediumvioletred" => Some((0xc7, 0x15, 0x85)),
+        "midnightblue" => Some((0x19, 0x19, 0x70)),
+        "mintcream" => Some((0xf5, 0xff, 0xfa)),
+        "mistyrose" => Some((0xff, 0xe4, 0xe1)),
+        "moccasin" => Some((0xff, 0xe4, 0xb5)),
+        "navajowhite" => Some((0xff, 0xde, 0xad)),
+        "navy" => Some((0x00, 0x00, 0x80)),
+        "oldlace" => Some((0xfd, 0xf5, 0xe6)),
+        "olive" => Some((0x80, 0x80, 0x00)),
+        "olivedrab" => Some((0x6b, 0x8e, 0x23)),
+        "orange" => Some((0xff, 0xa5, 0x00)),
+        "orangered" => Some((0xff, 0x45, 0x00)),
+        "orchid" => Some((0xda, 0x70, 0xd6)),
+        "palegoldenrod" => Some((0xee, 0xe8, 0xaa)),
+        "palegreen" => Some((0x98, 0xfb, 0x98)),
+        "paleturquoise" => Some((0xaf, 0xee, 0xee)),
+        "palevioletred" => Some((0xdb, 0x70, 0x93)),
+        "papayawhip" => Some((0xff, 0xef, 0xd5)),
+        "peachpuff" => Some((0xff, 0xda, 0xb9)),
+        "peru" => Some((0xcd, 0x85, 0x3f)),
+        "pink" => Some((0xff, 0xc0, 0xcb)),
+        "plum" => Some((0xdd, 0xa0, 0xdd)),
+        "powderblue" => Some((0xb0, 0xe0, 0xe6)),
+        "purple" => Some((0x80, 0x00, 0x80)),
+        "rebeccapurple" => Some((0x66, 0x33, 0x99)),
+        "red" => Some((0xff, 0x00, 0x00)),
+        "rosybrown" => Some((0xbc, 0x8f, 0x8f)),
+        "royalblue" => Some((0x41, 0x69, 0xe1)),
+        "saddlebrown" => Some((0x8b, 0x45, 0x13)),
+        "salmon" => Some((0xfa, 0x80, 0x72)),
+        "sandybrown" => Some((0xf4, 0xa4, 0x60)),
+        "seagreen" => Some((0x2e, 0x8b, 0x57)),
+        "seashell" => Some((0xff, 0xf5, 0xee)),
+        "sienna" => Some((0xa0, 0x52, 0x2d)),
+        "silver" => Some((0xc0, 0xc0, 0xc0)),
+        "skyblue" => Some((0x87, 0xce, 0xeb)),
+        "slateblue" => Some((0x6a, 0x5a, 0xcd)),
+        "slategray" => Some((0x70, 0x80, 0x90)),
+        "slategrey" => Some((0x70, 0x80, 0x90)),
+        "snow" => Some((0xff, 0xfa, 0xfa)),
+        "springgreen" => Some((0x00, 0xff, 0x7f)),
+        "steelblue" => Some((0x46, 0x82, 0xb4)),
+        "tan" => Some((0xd2, 0xb4, 0x8c)),
+        "teal" => Some((0x00, 0x80, 0x80)),
+        "thistle" => Some((0xd8, 0xbf, 0xd8)),
+        "tomato" => Some((0xff, 0x63, 0x47)),
+        "turquoise" => Some((0x40, 0xe0, 0xd0)),
+        "violet" => Some((0xee, 0x82, 0xee)),
+        "wheat" => Some((0xf5, 0xde, 0xb3)),
+        "white" => Some((0xff, 0xff, 0xff)),
+        "whitesmoke" => Some((0xf5, 0xf5, 0xf5)),
+        "yellow" => Some((0xff, 0xff, 0x00)),
+        "yellowgreen" => Some((0x9a, 0xcd, 0x32)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn color(s: &str) -> Color {
+        s.parse().expect("invalid color")
+    }
+
+    #[test]
+    fn hex_codes_expand_and_keep_alpha() {
+        assert_eq!(color("#f80"), Color::Rgba(0xff, 0x88, 0x00, 0xff));
+        assert_eq!(color("#FF8800"), Color::Rgba(0xff, 0x88, 0x00, 0xff));
+        assert_eq!(color("#ff880080"), Color::Rgba(0xff, 0x88, 0x00, 0x80));
+        assert_eq!(color("#ff8800").to_string(), "#FF8800");
+        assert_eq!(color("#ff880080").to_string(), "#FF880080");
+    }
+
+    #[test]
+    fn names_auto_and_transparent() {
+        assert_eq!(color("red"), Color::Rgba(0xff, 0x00, 0x00, 0xff));
+        assert_eq!(color(" RebeccaPurple "), Color::Rgba(0x66, 0x33, 0x99, 0xff));
+        assert_eq!(color("auto"), Color::Auto);
+        assert_eq!(color("transparent"), Color::Rgba(0, 0, 0, 0));
+    }
+
+    #[test]
+    fn invalid_colors_are_rejected() {
+        for invalid in &["#", "#12", "#1234", "#1234567", "#ggg", "#\u{e9}\u{e9}\u{e9}", "notacolor", ""] {
+            assert!(invalid.parse::<Color>().is_err(), "{} parsed", invalid);
+        }
+    }
+
+    #[test]
+    fn tint_modes_combine_channels() {
+        let base = Color::Rgba(200, 100, 0, 0xff);
+        assert_eq!(base.tint(Color::Rgba(100, 10, 0, 0), TintMode::Add), Color::Rgba(255, 110, 0, 0xff));
+        assert_eq!(base.tint(Color::Rgba(128, 255, 0, 0x80), TintMode::Multiply), Color::Rgba(100, 100, 0, 0xff));
+        assert_eq!(base.tint(Color::Rgba(50, 200, 0, 0x80), TintMode::Lighten), Color::Rgba(200, 200, 0, 0xff));
+        assert_eq!(base.tint(Color::Rgba(50, 200, 0, 0x80), TintMode::Darken), Color::Rgba(50, 100, 0, 0xff));
+
+        // Blending weighs the tint by its alpha, and keeps the base's alpha
+        let black = Color::Rgba(0, 0, 0, 0xff);
+        assert_eq!(black.tint(Color::Rgba(255, 255, 255, 0), TintMode::Blend), black);
+        assert_eq!(black.tint(Color::Rgba(255, 255, 255, 128), TintMode::Blend), Color::Rgba(128, 128, 128, 0xff));
+        assert_eq!(black.tint(Color::Rgba(255, 0, 255, 255), TintMode::Blend), Color::Rgba(255, 0, 255, 0xff));
+
+        assert_eq!(Color::Auto.tint(base, TintMode::Add), Color::Auto);
+    }
+}
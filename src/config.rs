@@ -1,43 +1,147 @@
 use de::*;
 use icons;
 use serde::de::{self, Deserialize, Deserializer};
-use toml::value;
+use toml::{self, value};
 use std::collections::HashMap as Map;
+use std::ffi::OsString;
 use std::marker::PhantomData;
 use std::ops::Deref;
+use std::path::PathBuf;
 use std::str::FromStr;
-use themes::{self, Theme};
+use themes::{self, SharedTheme, Theme};
+use theme_switcher::ThemeSwitchConfig;
 
 #[derive(Deserialize, Debug, Clone)]
 pub struct Config {
     #[serde(default = "icons::default", deserialize_with = "deserialize_icons")]
     pub icons: Map<String, String>,
-    #[serde(default = "themes::default", deserialize_with = "deserialize_themes")]
-    pub theme: Theme,
+    #[serde(default = "Config::default_theme", deserialize_with = "deserialize_shared_theme")]
+    pub theme: SharedTheme,
+    /// Theme to use while the light color scheme is active. Without it, `theme` is used at all times.
+    #[serde(default, deserialize_with = "deserialize_opt_themes")]
+    pub light_theme: Option<Theme>,
+    #[serde(default)]
+    pub theme_switch: ThemeSwitchConfig,
     #[serde(rename = "block", deserialize_with = "deserialize_blocks")]
-    pub blocks: Vec<(String, value::Value)>,
+    pub blocks: Vec<(String, SharedBlockConfig, value::Value)>,
+    /// Where to find sysfs, if not at `/sys`.
+    #[serde(default)]
+    pub sysfs_root: Option<PathBuf>,
+    /// Where to find procfs, if not at `/proc`.
+    #[serde(default)]
+    pub procfs_root: Option<PathBuf>,
+    /// The roots blocks read from, resolved from the two options above by `resolve_roots`.
+    #[serde(skip)]
+    pub roots: FsRoots,
+}
+
+/// Where blocks reading from the file system find sysfs and procfs.
+#[derive(Debug, Clone)]
+pub struct FsRoots {
+    pub sysfs: PathBuf,
+    pub procfs: PathBuf,
+}
+
+impl FsRoots {
+    /// Returns the location of `path` (e.g. `"class/net"`) inside sysfs.
+    pub fn sysfs_path(&self, path: &str) -> PathBuf {
+        self.sysfs.join(path)
+    }
+
+    /// Returns the location of `path` (e.g. `"meminfo"`) inside procfs.
+    pub fn procfs_path(&self, path: &str) -> PathBuf {
+        self.procfs.join(path)
+    }
+}
+
+impl Default for FsRoots {
+    fn default() -> Self {
+        FsRoots {
+            sysfs: PathBuf::from("/sys"),
+            procfs: PathBuf::from("/proc"),
+        }
+    }
+}
+
+/// Options available on every block. They are taken out of the block's table
+/// before the block itself sees its configuration.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct SharedBlockConfig {
+    /// Separator drawn in front of the block, overriding the one of the theme.
+    /// `"none"` suppresses the separator.
+    pub separator: Option<String>,
+
+    /// Draw the following block right after this one, without a separator in between.
+    #[serde(default)]
+    pub merge_with_next: bool,
+
+    /// Icons replacing the ones of the icon set for this block only.
+    #[serde(default)]
+    pub icons_overrides: Map<String, String>,
+}
+
+const SHARED_BLOCK_KEYS: &[&str] = &["separator", "merge_with_next", "icons_overrides"];
+
+impl Config {
+    fn default_theme() -> SharedTheme {
+        SharedTheme::new(themes::default())
+    }
+
+    /// Sets the file system roots. The environment variables `I3RS_SYSFS_ROOT`
+    /// and `I3RS_PROCFS_ROOT`, as looked up by `var`, take precedence over
+    /// `sysfs_root` and `procfs_root`.
+    pub fn resolve_roots<F>(&mut self, var: F)
+    where
+        F: Fn(&str) -> Option<OsString>,
+    {
+        let defaults = FsRoots::default();
+        self.roots = FsRoots {
+            sysfs: var("I3RS_SYSFS_ROOT")
+                .map(PathBuf::from)
+                .or_else(|| self.sysfs_root.clone())
+                .unwrap_or(defaults.sysfs),
+            procfs: var("I3RS_PROCFS_ROOT")
+                .map(PathBuf::from)
+                .or_else(|| self.procfs_root.clone())
+                .unwrap_or(defaults.procfs),
+        };
+    }
 }
 
 impl Default for Config {
     fn default() -> Self {
         Config {
             icons: icons::default(),
-            theme: themes::default(),
+            theme: Config::default_theme(),
+            light_theme: None,
+            theme_switch: ThemeSwitchConfig::default(),
             blocks: Vec::new(),
+            sysfs_root: None,
+            procfs_root: None,
+            roots: FsRoots::default(),
         }
     }
 }
 
-fn deserialize_blocks<'de, D>(deserializer: D) -> Result<Vec<(String, value::Value)>, D::Error>
+fn deserialize_blocks<'de, D>(deserializer: D) -> Result<Vec<(String, SharedBlockConfig, value::Value)>, D::Error>
 where
     D: Deserializer<'de>,
 {
-    let mut blocks: Vec<(String, value::Value)> = Vec::new();
+    let mut blocks: Vec<(String, SharedBlockConfig, value::Value)> = Vec::new();
     let raw_blocks: Vec<value::Table> = Deserialize::deserialize(deserializer)?;
     for mut entry in raw_blocks {
         if let Some(name) = entry.remove("block") {
             if let Some(name) = name.as_str() {
-                blocks.push((name.to_owned(), value::Value::Table(entry)))
+                let mut shared = value::Table::new();
+                for key in SHARED_BLOCK_KEYS {
+                    if let Some(option) = entry.remove(*key) {
+                        shared.insert((*key).to_owned(), option);
+                    }
+                }
+                let shared = SharedBlockConfig::deserialize(value::Value::Table(shared))
+                    .map_err(|e: toml::de::Error| de::Error::custom(format!("block '{}': {}", name, e)))?;
+                blocks.push((name.to_owned(), shared, value::Value::Table(entry)))
             }
         }
     }
@@ -50,7 +154,7 @@ where
     D: Deserializer<'de>,
 {
     map_type!(Icons, String;
-              s => Ok(Icons(icons::get_icons(s).ok_or_else(|| "cannot find specified icons")?)));
+              s => Ok(Icons(icons::get_icons(s)?)));
 
     deserializer.deserialize_any(MapType::<Icons, String>(PhantomData, PhantomData))
 }
@@ -68,5 +172,63 @@ where
             PhantomData,
         ))?;
 
-    Deserialize::deserialize(de::value::MapDeserializer::new(intermediary.into_iter()))
+    let theme: Theme = Deserialize::deserialize(de::value::MapDeserializer::new(intermediary.into_iter()))?;
+    theme.normalized().map_err(de::Error::custom)
+}
+
+fn deserialize_shared_theme<'de, D>(deserializer: D) -> Result<SharedTheme, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_themes(deserializer).map(SharedTheme::new)
+}
+
+fn deserialize_opt_themes<'de, D>(deserializer: D) -> Result<Option<Theme>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    deserialize_themes(deserializer).map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(sysfs_root: Option<&str>, procfs_root: Option<&str>) -> Config {
+        Config {
+            sysfs_root: sysfs_root.map(PathBuf::from),
+            procfs_root: procfs_root.map(PathBuf::from),
+            ..Config::default()
+        }
+    }
+
+    #[test]
+    fn roots_default_to_the_real_file_systems() {
+        let mut config = config(None, None);
+        config.resolve_roots(|_| None);
+
+        assert_eq!(config.roots.sysfs, PathBuf::from("/sys"));
+        assert_eq!(config.roots.procfs, PathBuf::from("/proc"));
+    }
+
+    #[test]
+    fn roots_come_from_the_configuration() {
+        let mut config = config(Some("/fixture/sys"), Some("/fixture/proc"));
+        config.resolve_roots(|_| None);
+
+        assert_eq!(config.roots.sysfs, PathBuf::from("/fixture/sys"));
+        assert_eq!(config.roots.procfs, PathBuf::from("/fixture/proc"));
+    }
+
+    #[test]
+    fn environment_overrides_configured_roots() {
+        let mut config = config(Some("/fixture/sys"), Some("/fixture/proc"));
+        config.resolve_roots(|name| match name {
+            "I3RS_SYSFS_ROOT" => Some(OsString::from("/env/sys")),
+            _ => None,
+        });
+
+        assert_eq!(config.roots.sysfs, PathBuf::from("/env/sys"));
+        assert_eq!(config.roots.procfs, PathBuf::from("/fixture/proc"));
+    }
 }
@@ -0,0 +1,434 @@
+//! One connection to each of the system and the session bus, shared by all
+//! blocks and owned by a dispatcher thread.
+//!
+//! Blocks subscribe to signals with match rules, which the dispatcher routes
+//! to them, and make method calls through it. The dispatcher sleeps until
+//! either the bus or a block has something for it. When the bus goes away, it
+//! reconnects and adds all match rules again.
+
+use chan::{self, Receiver, Sender};
+use errors::*;
+use listener::Backoff;
+use logging::spawn_thread;
+use std::os::unix::io::RawFd;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+extern crate dbus;
+extern crate nix;
+pub use self::dbus::Message;
+use self::dbus::{BusType, Connection, ConnectionItem, MessageType, WatchEvent};
+use self::nix::fcntl::OFlag;
+use self::nix::poll::{poll, EventFlags, PollFd};
+use self::nix::unistd::{pipe2, read, write};
+
+/// Timeout of method calls, in milliseconds.
+const CALL_TIMEOUT: i32 = 1000;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Bus {
+    System,
+    Session,
+}
+
+impl Bus {
+    fn name(self) -> &'static str {
+        match self {
+            Bus::System => "system bus",
+            Bus::Session => "session bus",
+        }
+    }
+}
+
+/// What subscribers are told about.
+pub enum DbusEvent<'a> {
+    Signal(&'a Message),
+    /// The dispatcher (re)connected to the bus, e.g. after the bus restarted.
+    Connected,
+    Disconnected,
+}
+
+/// Signals a subscriber is interested in. Unset fields match anything.
+#[derive(Debug, Clone, Default)]
+pub struct MatchRule {
+    interface: Option<String>,
+    member: Option<String>,
+    path: Option<String>,
+}
+
+impl MatchRule {
+    pub fn new() -> Self {
+        MatchRule::default()
+    }
+
+    pub fn with_interface(mut self, interface: &str) -> Self {
+        self.interface = Some(interface.to_owned());
+        self
+    }
+
+    pub fn with_member(mut self, member: &str) -> Self {
+        self.member = Some(member.to_owned());
+        self
+    }
+
+    pub fn with_path(mut self, path: &str) -> Self {
+        self.path = Some(path.to_owned());
+        self
+    }
+
+    /// The rule in the syntax of the bus daemon.
+    pub fn rule(&self) -> String {
+        let mut rule = "type='signal'".to_owned();
+        for &(key, ref value) in &[("interface", &self.interface), ("member", &self.member), ("path", &self.path)] {
+            if let Some(ref value) = **value {
+                rule.push_str(&format!(",{}='{}'", key, value));
+            }
+        }
+        rule
+    }
+
+    pub fn matches(&self, message: &Message) -> bool {
+        fn field_matches(expected: &Option<String>, actual: Option<String>) -> bool {
+            expected.as_ref().map_or(true, |expected| actual.as_ref() == Some(expected))
+        }
+
+        message.msg_type() == MessageType::Signal &&
+            field_matches(&self.interface, message.interface().map(|i| (&*i).to_owned())) &&
+            field_matches(&self.member, message.member().map(|m| (&*m).to_owned())) &&
+            field_matches(&self.path, message.path().map(|p| (&*p).to_owned()))
+    }
+}
+
+/// Whether `message` is the signal libdbus sends locally when the connection
+/// to the bus broke.
+fn is_disconnect(message: &Message) -> bool {
+    MatchRule::new()
+        .with_interface("org.freedesktop.DBus.Local")
+        .with_member("Disconnected")
+        .matches(message)
+}
+
+type Handler = Box<FnMut(DbusEvent) + Send>;
+
+enum Request {
+    Subscribe(MatchRule, Handler),
+    Call(Message, Sender<Result<Message>>),
+    Send(Message),
+}
+
+struct Dispatcher {
+    requests: Mutex<Sender<Request>>,
+    /// Write end of the pipe waking the dispatcher thread up.
+    wake: RawFd,
+    connected: Arc<AtomicBool>,
+}
+
+impl Dispatcher {
+    /// Starts the dispatcher thread, and waits for its first attempt to connect.
+    fn start(bus: Bus) -> Result<Self> {
+        let (tx_requests, rx_requests) = chan::async();
+        // Neither end blocks: waking the thread must not block the blocks, and
+        // the thread empties the pipe before handling requests.
+        let (wake_read, wake_write) = pipe2(OFlag::O_NONBLOCK | OFlag::O_CLOEXEC)
+            .internal_error("dbus", "failed to create the D-Bus dispatcher's pipe")?;
+        let connected = Arc::new(AtomicBool::new(false));
+        let (tx_started, rx_started) = chan::sync(1);
+
+        let thread_connected = connected.clone();
+        spawn_thread("dbus", move || {
+            let mut dispatch = Dispatch {
+                bus,
+                requests: rx_requests,
+                wake: wake_read,
+                connected: thread_connected,
+                subscriptions: Vec::new(),
+            };
+            dispatch.run(tx_started);
+            Ok(())
+        });
+        rx_started.recv();
+
+        Ok(Dispatcher {
+            requests: Mutex::new(tx_requests),
+            wake: wake_write,
+            connected,
+        })
+    }
+
+    fn request(&self, request: Request) {
+        self.requests.lock().unwrap_or_else(|e| e.into_inner()).send(request);
+        // A full pipe is awake already
+        let _ = write(self.wake, &[0]);
+    }
+}
+
+lazy_static! {
+    static ref SYSTEM: Result<Dispatcher> = Dispatcher::start(Bus::System);
+    static ref SESSION: Result<Dispatcher> = Dispatcher::start(Bus::Session);
+}
+
+fn dispatcher(bus: Bus) -> Result<&'static Dispatcher> {
+    let dispatcher = match bus {
+        Bus::System => &*SYSTEM,
+        Bus::Session => &*SESSION,
+    };
+    match *dispatcher {
+        Ok(ref dispatcher) => Ok(dispatcher),
+        Err(ref error) => Err(InternalError(
+            "dbus".to_owned(),
+            format!("the dispatcher of the {} failed to start: {}", bus.name(), error),
+            None,
+        )),
+    }
+}
+
+/// The state of the dispatcher thread.
+struct Dispatch {
+    bus: Bus,
+    requests: Receiver<Request>,
+    wake: RawFd,
+    connected: Arc<AtomicBool>,
+    subscriptions: Vec<(MatchRule, Handler)>,
+}
+
+impl Dispatch {
+    fn run(&mut self, started: Sender<()>) {
+        let mut started = Some(started);
+        let mut backoff = Backoff::new();
+        loop {
+            let connection = match self.bus {
+                Bus::System => Connection::get_private(BusType::System),
+                Bus::Session => Connection::get_private(BusType::Session),
+            };
+            match connection {
+                Ok(c) => {
+                    backoff.reset();
+                    self.connected.store(true, Ordering::SeqCst);
+                    info!("dbus", "connected to the {}", self.bus.name());
+                    for &mut (ref rule, ref mut handler) in &mut self.subscriptions {
+                        add_match(&c, rule);
+                        handler(DbusEvent::Connected);
+                    }
+                    if let Some(started) = started.take() {
+                        started.send(());
+                    }
+
+                    match self.serve(&c) {
+                        Ok(()) => warn!("dbus", "lost the connection to the {}", self.bus.name()),
+                        Err(error) => warn!("dbus", "lost the connection to the {}: {:?}", self.bus.name(), error),
+                    }
+                    self.connected.store(false, Ordering::SeqCst);
+                    for &mut (_, ref mut handler) in &mut self.subscriptions {
+                        handler(DbusEvent::Disconnected);
+                    }
+                }
+                Err(error) => {
+                    warn!("dbus", "failed to connect to the {}: {}", self.bus.name(), error);
+                    if let Some(started) = started.take() {
+                        started.send(());
+                    }
+                }
+            }
+
+            self.wait_disconnected(backoff.next());
+        }
+    }
+
+    /// Routes signals and handles requests until the connection breaks.
+    fn serve(&mut self, c: &Connection) -> Result<()> {
+        loop {
+            let watches = c.watch_fds();
+            let mut fds: Vec<PollFd> = watches
+                .iter()
+                .map(|watch| {
+                    let mut flags = EventFlags::empty();
+                    if watch.readable() {
+                        flags |= EventFlags::POLLIN;
+                    }
+                    if watch.writable() {
+                        flags |= EventFlags::POLLOUT;
+                    }
+                    PollFd::new(watch.fd(), flags)
+                })
+                .collect();
+            fds.push(PollFd::new(self.wake, EventFlags::POLLIN));
+
+            poll(&mut fds, -1).internal_error("dbus", "failed to wait for D-Bus messages")?;
+
+            for (watch, fd) in watches.iter().zip(fds.iter()) {
+                let revents = match fd.revents() {
+                    Some(revents) if !revents.is_empty() => revents,
+                    _ => continue,
+                };
+                for item in c.watch_handle(watch.fd(), WatchEvent::from_revents(revents.bits())) {
+                    if let ConnectionItem::Signal(ref message) = item {
+                        if is_disconnect(message) {
+                            return Ok(());
+                        }
+                        self.route(message);
+                    }
+                }
+            }
+
+            if fds.last().and_then(|fd| fd.revents()).map_or(false, |revents| !revents.is_empty()) {
+                self.handle_requests(Some(c));
+                // Method calls leave the signals that arrived meanwhile queued
+                for message in c.incoming(0) {
+                    if is_disconnect(&message) {
+                        return Ok(());
+                    }
+                    self.route(&message);
+                }
+            }
+        }
+    }
+
+    fn route(&mut self, message: &Message) {
+        for &mut (ref rule, ref mut handler) in &mut self.subscriptions {
+            if rule.matches(message) {
+                handler(DbusEvent::Signal(message));
+            }
+        }
+    }
+
+    /// Handles all pending requests, on the connection `c` if there is one.
+    fn handle_requests(&mut self, c: Option<&Connection>) {
+        let mut buffer = [0; 64];
+        let size = buffer.len();
+        while read(self.wake, &mut buffer).map_or(false, |n| n == size) {}
+
+        loop {
+            let mut request = None;
+            chan_select! {
+                default => {},
+                self.requests.recv() -> res => {
+                    request = res;
+                },
+            }
+
+            match (request, c) {
+                (None, _) => break,
+                (Some(Request::Subscribe(rule, handler)), c) => {
+                    // The rule is added once connected otherwise
+                    if let Some(c) = c {
+                        add_match(c, &rule);
+                    }
+                    self.subscriptions.push((rule, handler));
+                }
+                (Some(Request::Call(message, reply)), Some(c)) => {
+                    reply.send(c.send_with_reply_and_block(message, CALL_TIMEOUT)
+                        .internal_error("dbus", "D-Bus method call failed"));
+                }
+                (Some(Request::Call(_, reply)), None) => {
+                    reply.send(Err(InternalError(
+                        "dbus".to_owned(),
+                        format!("not connected to the {}", self.bus.name()),
+                        None,
+                    )));
+                }
+                (Some(Request::Send(message)), Some(c)) => {
+                    if c.send(message).is_err() {
+                        warn!("dbus", "failed to send a message on the {}", self.bus.name());
+                    }
+                }
+                (Some(Request::Send(_)), None) => {
+                    warn!("dbus", "dropping a message while not connected to the {}", self.bus.name());
+                }
+            }
+        }
+    }
+
+    /// Waits for `delay` before reconnecting, answering requests in the meantime.
+    fn wait_disconnected(&mut self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut now = Instant::now();
+        while now < until {
+            let timeout = until - now;
+            let mut fds = [PollFd::new(self.wake, EventFlags::POLLIN)];
+            let millis = timeout.as_secs() as i32 * 1000 + (timeout.subsec_nanos() / 1_000_000) as i32;
+            if poll(&mut fds, millis).map(|ready| ready > 0).unwrap_or(false) {
+                self.handle_requests(None);
+            }
+            now = Instant::now();
+        }
+    }
+}
+
+fn add_match(c: &Connection, rule: &MatchRule) {
+    if let Err(error) = c.add_match(&rule.rule()) {
+        warn!("dbus", "failed to add match rule {}: {}", rule.rule(), error);
+    }
+}
+
+/// Calls `handler` for each signal on `bus` matching `rule`, and when the
+/// connection to the bus is lost or established again. `handler` runs on the
+/// dispatcher thread, so it must neither block nor call into the dispatcher.
+pub fn subscribe<F>(bus: Bus, rule: MatchRule, handler: F) -> Result<()>
+where
+    F: FnMut(DbusEvent) + Send + 'static,
+{
+    dispatcher(bus)?.request(Request::Subscribe(rule, Box::new(handler)));
+    Ok(())
+}
+
+/// Sends the method call `message` on `bus` and waits for the reply.
+pub fn call(bus: Bus, message: Message) -> Result<Message> {
+    let dispatcher = dispatcher(bus)?;
+    if !dispatcher.connected.load(Ordering::SeqCst) {
+        return Err(InternalError(
+            "dbus".to_owned(),
+            format!("not connected to the {}", bus.name()),
+            None,
+        ));
+    }
+
+    let (tx_reply, rx_reply) = chan::sync(1);
+    dispatcher.request(Request::Call(message, tx_reply));
+    rx_reply
+        .recv()
+        .internal_error("dbus", "the D-Bus dispatcher stopped")?
+}
+
+/// Sends `message` on `bus` without waiting for a reply.
+pub fn send(bus: Bus, message: Message) -> Result<()> {
+    dispatcher(bus)?.request(Request::Send(message));
+    Ok(())
+}
+
+/// Reads `property` of `interface` of the object at `path` of `destination`.
+/// The reply holds the value as a variant.
+pub fn get_property(bus: Bus, destination: &str, path: &str, interface: &str, property: &str) -> Result<Message> {
+    let message = Message::new_method_call(destination, path, "org.freedesktop.DBus.Properties", "Get")
+        .internal_error("dbus", "failed to create D-Bus method call")?
+        .append2(interface, property);
+    call(bus, message)
+}
+
+pub fn is_connected(bus: Bus) -> bool {
+    dispatcher(bus).map_or(false, |dispatcher| dispatcher.connected.load(Ordering::SeqCst))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dbus_match_rules_list_the_given_fields() {
+        let rule = MatchRule::new()
+            .with_interface("org.freedesktop.DBus.Properties")
+            .with_member("PropertiesChanged");
+        assert_eq!(
+            rule.rule(),
+            "type='signal',interface='org.freedesktop.DBus.Properties',member='PropertiesChanged'"
+        );
+
+        let signal = Message::signal(
+            &"/org/mpris/MediaPlayer2".into(),
+            &"org.freedesktop.DBus.Properties".into(),
+            &"PropertiesChanged".into(),
+        );
+        assert!(rule.matches(&signal));
+        assert!(!rule.with_path("/org/freedesktop/UPower").matches(&signal));
+    }
+}
@@ -191,3 +191,43 @@ where
     let s = String::deserialize(deserializer)?;
     Tz::from_str(&s).map(Some).map_err(de::Error::custom)
 }
+
+/// Deserializes either a single string or a list of strings.
+pub fn deserialize_string_or_list<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+where
+    D: Deserializer<'de>,
+{
+    struct StringOrList;
+
+    impl<'de> de::Visitor<'de> for StringOrList {
+        type Value = Vec<String>;
+
+        fn expecting(&self, formatter: &mut fmt::Formatter) -> fmt::Result {
+            formatter.write_str("string or seq of strings")
+        }
+
+        fn visit_str<E>(self, value: &str) -> Result<Self::Value, E>
+        where
+            E: de::Error,
+        {
+            Ok(vec![value.to_owned()])
+        }
+
+        fn visit_seq<A>(self, mut visitor: A) -> Result<Self::Value, A::Error>
+        where
+            A: de::SeqAccess<'de>,
+        {
+            let mut values = Vec::new();
+            while let Some(value) = visitor.next_element()? {
+                values.push(value);
+            }
+            if values.is_empty() {
+                Err(de::Error::custom("seq is empty"))
+            } else {
+                Ok(values)
+            }
+        }
+    }
+
+    deserializer.deserialize_any(StringOrList)
+}
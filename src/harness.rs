@@ -0,0 +1,374 @@
+//! Drives the bar like i3bar would, with scripted click events, a mock clock
+//! and blocks reading sysfs and procfs from fixture directories.
+
+use bar::{create_blocks, Bar, BarEvent};
+use chan::{self, Receiver, Sender};
+use config::{Config, FsRoots};
+use input::parse_event;
+use scheduler::{Clock, Task};
+use serde_json::value::Value;
+use std::cell::Cell;
+use std::fs;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use theme_switcher::ThemeSwitcher;
+use themes::SharedTheme;
+use toml;
+
+/// A clock that only moves when told to, or when the scheduler sleeps.
+#[derive(Clone)]
+pub struct MockClock(Rc<Cell<Instant>>);
+
+impl MockClock {
+    pub fn new() -> Self {
+        MockClock(Rc::new(Cell::new(Instant::now())))
+    }
+
+    pub fn set(&self, time: Instant) {
+        self.0.set(time)
+    }
+}
+
+impl Clock for MockClock {
+    fn now(&self) -> Instant {
+        self.0.get()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        self.0.set(self.0.get() + duration)
+    }
+}
+
+pub enum Step<'a> {
+    /// A line written by i3bar to our stdin.
+    Input(&'a str),
+    /// Let time pass, running all updates scheduled in the meantime.
+    Advance(Duration),
+    /// Anything else, e.g. changing fixture files.
+    Run(&'a Fn()),
+}
+
+/// Returns the directory with the sysfs and procfs fixtures called `name`.
+pub fn fixture(name: &str) -> FsRoots {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name);
+    FsRoots {
+        sysfs: dir.join("sys"),
+        procfs: dir.join("proc"),
+    }
+}
+
+/// A copy of a fixture, removed again when dropped.
+pub struct WritableFixture {
+    dir: PathBuf,
+    roots: FsRoots,
+}
+
+impl Deref for WritableFixture {
+    type Target = FsRoots;
+
+    fn deref(&self) -> &FsRoots {
+        &self.roots
+    }
+}
+
+impl Drop for WritableFixture {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.dir);
+    }
+}
+
+/// Copies the fixture called `name` to a fresh directory, so tests can modify it.
+pub fn writable_fixture(name: &str) -> WritableFixture {
+    fn copy_dir(from: &Path, to: &Path) {
+        fs::create_dir_all(to).expect("failed to create fixture directory");
+        for entry in fs::read_dir(from).expect("failed to read fixture directory") {
+            let entry = entry.expect("failed to read fixture directory");
+            let target = to.join(entry.file_name());
+            if entry.path().is_dir() {
+                copy_dir(&entry.path(), &target);
+            } else {
+                fs::copy(entry.path(), target).expect("failed to copy fixture");
+            }
+        }
+    }
+
+    let source = fixture(name);
+    let dir = ::std::env::temp_dir().join(format!("i3status-rs-{}-{}", name, ::uuid::Uuid::new_v4().simple()));
+    let roots = FsRoots {
+        sysfs: dir.join("sys"),
+        procfs: dir.join("proc"),
+    };
+    copy_dir(&source.sysfs, &roots.sysfs);
+    copy_dir(&source.procfs, &roots.procfs);
+    WritableFixture { dir, roots }
+}
+
+/// Runs the bar with the configuration `config` through `script`, starting
+/// with the first scheduled updates. Returns all frames written.
+pub fn run_script(config: &str, roots: FsRoots, script: &[Step]) -> Vec<Vec<Value>> {
+    let mut config: Config = toml::from_str(config).expect("invalid test configuration");
+    config.roots = roots;
+
+    let (tx_update_requests, rx_update_requests): (Sender<Task>, Receiver<Task>) = chan::async();
+    let alternating_theme = SharedTheme::new(config
+        .theme
+        .get()
+        .alternating_tint()
+        .expect("invalid alternating tint"));
+    let theme_switcher = ThemeSwitcher::new(&config, alternating_theme.clone());
+    let mut blocks = create_blocks(&config, &alternating_theme, &tx_update_requests).expect("failed to create blocks");
+
+    let clock = MockClock::new();
+    let mut bar = Bar::new(&mut blocks, &config, theme_switcher, Box::new(clock.clone()));
+    let mut out: Vec<u8> = Vec::new();
+
+    let start = Step::Advance(Duration::from_secs(0));
+    for step in Some(&start).into_iter().chain(script.iter()) {
+        match *step {
+            Step::Input(line) => {
+                if let Some(event) = parse_event(line) {
+                    bar.handle(BarEvent::Click(event), &mut out).expect("click failed");
+                }
+            }
+            Step::Advance(duration) => {
+                let target = clock.now() + duration;
+                while let Some(next) = bar.time_to_next_update() {
+                    if clock.now() + next > target {
+                        break;
+                    }
+                    bar.handle(BarEvent::ScheduledUpdate, &mut out).expect("update failed");
+                }
+                clock.set(target);
+            }
+            Step::Run(f) => f(),
+        }
+
+        // Blocks may have asked for updates in the meantime
+        loop {
+            let mut request = None;
+            chan_select! {
+                default => {},
+                rx_update_requests.recv() -> res => {
+                    request = res;
+                },
+            }
+            match request {
+                Some(request) => bar.handle(BarEvent::UpdateRequest(request), &mut out).expect("update failed"),
+                None => break,
+            }
+        }
+    }
+
+    String::from_utf8(out)
+        .expect("frames are not UTF-8")
+        .lines()
+        .map(|line| ::serde_json::from_str(line.trim_right_matches(',')).expect("frame is not valid JSON"))
+        .collect()
+}
+
+/// The texts shown in `frame`, in order.
+pub fn texts(frame: &[Value]) -> Vec<&str> {
+    frame
+        .iter()
+        .map(|widget| widget["full_text"].as_str().expect("widget without full_text"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Plain theme without separators, so frames only hold the blocks' widgets.
+    const HEADER: &str = r#"
+theme = { name = "plain", overrides = { separator = "none" } }
+icons = "none"
+"#;
+
+    fn config(blocks: &str) -> String {
+        format!("{}\n{}", HEADER, blocks)
+    }
+
+    fn last(frames: &[Vec<Value>]) -> &[Value] {
+        frames.last().expect("no frame written")
+    }
+
+    #[test]
+    fn first_frame_shows_all_blocks() {
+        let frames = run_script(
+            &config(
+                r#"
+[[block]]
+block = "uptime"
+
+[[block]]
+block = "battery"
+
+[[block]]
+block = "backlight"
+"#,
+            ),
+            fixture("laptop"),
+            &[],
+        );
+
+        assert_eq!(frames.len(), 1);
+        assert_eq!(texts(last(&frames)), vec![" UP 1d 2h ", " DCG 42% ", " BRIGHT 60% "]);
+    }
+
+    #[test]
+    fn battery_combines_all_batteries() {
+        let frames = run_script(
+            &config("[[block]]\nblock = \"battery\"\ndevice = \"all\"\nformat = \"{percentage}% {percentages}\"\n"),
+            fixture("laptop"),
+            &[],
+        );
+
+        assert_eq!(texts(last(&frames)), vec![" DCG 31% 42/20 "]);
+    }
+
+    #[test]
+    fn battery_shows_health_and_hides_on_ac() {
+        let roots = writable_fixture("laptop");
+        let block = "[[block]]\nblock = \"battery\"\nformat = \"{health} {status}\"\nhide_when_on_ac = true\n";
+
+        let frames = run_script(&config(block), roots.clone(), &[]);
+        assert_eq!(texts(last(&frames)), vec![" DCG 84 Discharging "]);
+
+        fs::write(roots.sysfs_path("class/power_supply/AC/online"), "1\n").expect("failed to write fixture");
+        let frames = run_script(&config(block), roots.clone(), &[]);
+        assert!(texts(last(&frames)).is_empty());
+    }
+
+    #[test]
+    fn battery_hides_only_when_full() {
+        let roots = writable_fixture("laptop");
+        let block = "[[block]]\nblock = \"battery\"\nformat = \"{percentage}% {status}\"\nhide_when_full = true\n";
+        let status = roots.sysfs_path("class/power_supply/BAT0/status");
+
+        fs::write(&status, "Not charging\n").expect("failed to write fixture");
+        let frames = run_script(&config(block), roots.clone(), &[]);
+        assert_eq!(texts(last(&frames)), vec![" BAT 42% Not charging "]);
+
+        fs::write(&status, "Full\n").expect("failed to write fixture");
+        let frames = run_script(&config(block), roots.clone(), &[]);
+        assert!(texts(last(&frames)).is_empty());
+    }
+
+    #[test]
+    fn hidden_blocks_pass_on_merging_only_if_they_merge() {
+        let roots = writable_fixture("laptop");
+        fs::write(roots.sysfs_path("class/power_supply/BAT0/status"), "Full\n").expect("failed to write fixture");
+        let blocks = |battery_merges: bool| {
+            format!(
+                r#"
+theme = {{ name = "plain", overrides = {{ separator = "|" }} }}
+icons = "none"
+
+[[block]]
+block = "uptime"
+merge_with_next = true
+
+[[block]]
+block = "battery"
+hide_when_full = true
+merge_with_next = {}
+
+[[block]]
+block = "backlight"
+"#,
+                battery_merges
+            )
+        };
+
+        let frames = run_script(&blocks(false), roots.clone(), &[]);
+        assert_eq!(texts(last(&frames)), vec!["|", " UP 1d 2h ", "|", " BRIGHT 60% "]);
+
+        let frames = run_script(&blocks(true), roots.clone(), &[]);
+        assert_eq!(texts(last(&frames)), vec!["|", " UP 1d 2h ", " BRIGHT 60% "]);
+    }
+
+    #[test]
+    fn memory_switches_to_swap_on_click() {
+        let frames = run_script(
+            &config(
+                r#"
+[[block]]
+block = "memory"
+format_mem = "{MFm}/{MTm}"
+format_swap = "{SFm}/{STm}"
+"#,
+            ),
+            fixture("laptop"),
+            &[
+                Step::Input("["),
+                Step::Input(r#"{"name":"memory","button":1,"x":10,"y":10}"#),
+            ],
+        );
+
+        assert_eq!(texts(&frames[0]), vec![" MEM 4096/8192 "]);
+        assert_eq!(texts(last(&frames)), vec![" SWAP 1024/2048 "]);
+    }
+
+    #[test]
+    fn clicks_on_other_blocks_are_ignored() {
+        let frames = run_script(
+            &config("[[block]]\nblock = \"memory\"\nformat_mem = \"{MTm}\"\n"),
+            fixture("laptop"),
+            &[Step::Input(r#",{"name":"time","button":1,"x":10,"y":10}"#)],
+        );
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(texts(last(&frames)), vec![" MEM 8192 "]);
+    }
+
+    #[test]
+    fn net_shows_down_device() {
+        let frames = run_script(
+            &config("[[block]]\nblock = \"net\"\ndevice = \"eth0\"\nspeed_up = false\nspeed_down = false\n"),
+            fixture("laptop"),
+            &[],
+        );
+
+        assert_eq!(texts(last(&frames)), vec![" ETH × "]);
+        assert_eq!(last(&frames)[0]["color"], Value::String("#dc322f".to_owned()));
+    }
+
+    #[test]
+    fn load_reads_procfs_root() {
+        let frames = run_script(&config("[[block]]\nblock = \"load\"\n"), fixture("laptop"), &[]);
+
+        assert_eq!(texts(last(&frames)), vec![" LOAD 0.50 "]);
+    }
+
+    #[test]
+    fn self_shows_own_memory() {
+        let frames = run_script(&config("[[block]]\nblock = \"self\"\nformat = \"{rss}\"\n"), fixture("laptop"), &[]);
+
+        let rss: f64 = texts(last(&frames))[0].trim().parse().expect("rss is not a number");
+        assert!(rss > 0f64);
+    }
+
+    #[test]
+    fn cpu_updates_on_schedule() {
+        let roots = writable_fixture("laptop");
+        let stat = roots.procfs_path("stat");
+        let busy = || fs::write(&stat, "cpu  1250 0 0 750 0 0 0 0 0 0\n").expect("failed to write fixture");
+
+        let frames = run_script(
+            &config("[[block]]\nblock = \"cpu\"\ninterval = 2\n"),
+            roots.clone(),
+            &[
+                Step::Run(&busy),
+                // Not due yet
+                Step::Advance(Duration::from_secs(1)),
+                Step::Advance(Duration::from_secs(1)),
+            ],
+        );
+
+        assert_eq!(frames.len(), 2);
+        assert_eq!(texts(&frames[0]), vec![" CPU 25% "]);
+        assert_eq!(texts(&frames[1]), vec![" CPU 100% "]);
+    }
+}
@@ -0,0 +1,264 @@
+//! One subscription to the events of i3 (or sway), shared by all blocks, and a
+//! connection to query i3 and send it commands.
+//!
+//! A listener thread subscribes to all events blocks may need and hands each
+//! one to the blocks interested in its kind. When i3 goes away, e.g. on a
+//! restart, it reconnects and tells the blocks.
+
+use errors::*;
+use listener::Backoff;
+use logging::spawn_thread;
+use std::env;
+use std::mem;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+extern crate i3ipc;
+pub use self::i3ipc::event::Event;
+pub use self::i3ipc::reply::{Node, Workspaces};
+use self::i3ipc::{I3Connection, I3EventListener, Subscription};
+
+/// The kinds of events blocks can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EventKind {
+    Window,
+    Workspace,
+    Binding,
+    Mode,
+    Output,
+}
+
+impl EventKind {
+    fn of(event: &Event) -> Option<EventKind> {
+        match *event {
+            Event::WindowEvent(_) => Some(EventKind::Window),
+            Event::WorkspaceEvent(_) => Some(EventKind::Workspace),
+            Event::BindingEvent(_) => Some(EventKind::Binding),
+            Event::ModeEvent(_) => Some(EventKind::Mode),
+            Event::OutputEvent(_) => Some(EventKind::Output),
+            _ => None,
+        }
+    }
+}
+
+/// What subscribers are told about.
+#[derive(Clone, Copy)]
+pub enum I3Event<'a> {
+    Event(&'a Event),
+    /// The listener (re)connected to i3, e.g. after i3 restarted.
+    Connected,
+    Disconnected,
+}
+
+type Handler = Box<FnMut(I3Event) + Send>;
+
+type Subscriptions = Mutex<Vec<(Vec<EventKind>, Handler)>>;
+
+struct Hub {
+    subscriptions: Arc<Subscriptions>,
+    connected: Arc<AtomicBool>,
+    /// The connection for queries and commands, opened on first use.
+    commands: Mutex<Option<I3Connection>>,
+}
+
+impl Hub {
+    fn start() -> Self {
+        // sway speaks the same protocol, but announces its socket elsewhere
+        if env::var_os("I3SOCK").is_none() {
+            if let Some(socket) = env::var_os("SWAYSOCK") {
+                env::set_var("I3SOCK", socket);
+            }
+        }
+
+        let subscriptions: Arc<Subscriptions> = Arc::new(Mutex::new(Vec::new()));
+        // Blocks are assumed to be connected until the first attempt fails,
+        // so they don't show up as disconnected while the listener starts
+        let connected = Arc::new(AtomicBool::new(true));
+
+        let thread_subscriptions = subscriptions.clone();
+        let thread_connected = connected.clone();
+        spawn_thread("i3", move || {
+            let mut backoff = Backoff::new();
+            loop {
+                if let Err(error) = listen(&thread_subscriptions, &thread_connected, &mut backoff) {
+                    warn!("i3", "lost the connection to i3: {:?}", error);
+                }
+                if thread_connected.swap(false, Ordering::SeqCst) {
+                    notify(&thread_subscriptions, I3Event::Disconnected, |_| true);
+                }
+                thread::sleep(backoff.next());
+            }
+        });
+
+        Hub {
+            subscriptions,
+            connected,
+            commands: Mutex::new(None),
+        }
+    }
+}
+
+lazy_static! {
+    static ref HUB: Hub = Hub::start();
+}
+
+/// Calls the handlers of the subscriptions `wanted` accepts the kinds of. They
+/// run without the lock held, so that blocks can subscribe meanwhile, and a
+/// handler which panics is logged and dropped.
+fn notify<P: Fn(&[EventKind]) -> bool>(subscriptions: &Subscriptions, event: I3Event, wanted: P) {
+    let taken = mem::replace(&mut *subscriptions.lock().unwrap_or_else(|e| e.into_inner()), Vec::new());
+    let mut kept = Vec::with_capacity(taken.len());
+    for (kinds, mut handler) in taken {
+        if wanted(&kinds) && panic::catch_unwind(AssertUnwindSafe(|| handler(event))).is_err() {
+            error!("i3", "an event handler panicked, unsubscribing it");
+            continue;
+        }
+        kept.push((kinds, handler));
+    }
+
+    let mut subscriptions = subscriptions.lock().unwrap_or_else(|e| e.into_inner());
+    let added = mem::replace(&mut *subscriptions, kept);
+    subscriptions.extend(added);
+}
+
+/// Connects, subscribes and routes events until the connection breaks.
+fn listen(
+    subscriptions: &Subscriptions,
+    connected: &AtomicBool,
+    backoff: &mut Backoff,
+) -> Result<()> {
+    let mut listener = I3EventListener::connect().internal_error("i3", "failed to connect to i3")?;
+    listener
+        .subscribe(&[
+            Subscription::Window,
+            Subscription::Workspace,
+            Subscription::Binding,
+            Subscription::Mode,
+            Subscription::Output,
+        ])
+        .internal_error("i3", "failed to subscribe to i3 events")?;
+
+    backoff.reset();
+    connected.store(true, Ordering::SeqCst);
+    info!("i3", "connected to i3");
+    notify(subscriptions, I3Event::Connected, |_| true);
+
+    for event in listener.listen() {
+        let event = event.internal_error("i3", "failed to read i3 event")?;
+        let kind = match EventKind::of(&event) {
+            Some(kind) => kind,
+            None => continue,
+        };
+        notify(subscriptions, I3Event::Event(&event), |kinds| kinds.contains(&kind));
+    }
+    Ok(())
+}
+
+/// Calls `handler` for each i3 event of one of `kinds`, and when the
+/// connection to i3 is lost or established again. `handler` runs on the
+/// listener thread without holding any lock, so it may subscribe more
+/// handlers, but it must not block, e.g. by querying i3.
+pub fn subscribe<F>(kinds: &[EventKind], handler: F)
+where
+    F: FnMut(I3Event) + Send + 'static,
+{
+    HUB.subscriptions
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push((kinds.to_vec(), Box::new(handler)));
+}
+
+pub fn is_connected() -> bool {
+    HUB.connected.load(Ordering::SeqCst)
+}
+
+/// Runs `f` on the command connection, connecting first if needed. A
+/// connection that fails to send is replaced once, as i3 may have restarted
+/// since the last use. Other failures aren't retried, since i3 may have run
+/// the command already.
+fn with_connection<T, F>(what: &str, mut f: F) -> Result<T>
+where
+    F: FnMut(&mut I3Connection) -> ::std::result::Result<T, i3ipc::MessageError>,
+{
+    let mut commands = HUB.commands.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(ref mut connection) = *commands {
+        match f(connection) {
+            Err(i3ipc::MessageError::Send(_)) => {}
+            result => return result.internal_error("i3", &format!("failed to {}", what)),
+        }
+    }
+
+    *commands = None;
+    let mut connection = I3Connection::connect().internal_error("i3", "failed to connect to i3")?;
+    let result = f(&mut connection).internal_error("i3", &format!("failed to {}", what));
+    *commands = Some(connection);
+    result
+}
+
+/// Runs `command` like `i3-msg` would.
+pub fn run_command(command: &str) -> Result<()> {
+    let reply = with_connection("run i3 command", |connection| connection.run_command(command))?;
+    match reply.outcomes.into_iter().find(|outcome| !outcome.success) {
+        Some(outcome) => Err(InternalError(
+            "i3".to_owned(),
+            format!(
+                "i3 command '{}' failed: {}",
+                command,
+                outcome.error.unwrap_or_default()
+            ),
+            None,
+        )),
+        None => Ok(()),
+    }
+}
+
+pub fn get_workspaces() -> Result<Workspaces> {
+    with_connection("get i3 workspaces", |connection| connection.get_workspaces())
+}
+
+pub fn get_tree() -> Result<Node> {
+    with_connection("get i3 tree", |connection| connection.get_tree())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn handler<F: FnMut(I3Event) + Send + 'static>(f: F) -> Handler {
+        Box::new(f)
+    }
+
+    #[test]
+    fn i3_handlers_that_panic_are_unsubscribed() {
+        let subscriptions: Arc<Subscriptions> = Arc::new(Mutex::new(Vec::new()));
+        let calls = Arc::new(Mutex::new(0));
+
+        let handler_calls = calls.clone();
+        let handler_subscriptions = subscriptions.clone();
+        let mut subscribed = false;
+        subscriptions.lock().unwrap().push((
+            vec![EventKind::Window],
+            handler(move |_| {
+                *handler_calls.lock().unwrap() += 1;
+                // Subscribing from a handler doesn't deadlock
+                if !subscribed {
+                    subscribed = true;
+                    handler_subscriptions.lock().unwrap().push((vec![EventKind::Mode], handler(|_| {})));
+                }
+            }),
+        ));
+        subscriptions
+            .lock()
+            .unwrap()
+            .push((vec![EventKind::Window], handler(|_| panic!("handler failed"))));
+
+        notify(&subscriptions, I3Event::Connected, |kinds| kinds.contains(&EventKind::Window));
+        notify(&subscriptions, I3Event::Connected, |kinds| kinds.contains(&EventKind::Window));
+
+        assert_eq!(*calls.lock().unwrap(), 2);
+        let kinds: Vec<Vec<EventKind>> = subscriptions.lock().unwrap().iter().map(|&(ref kinds, _)| kinds.clone()).collect();
+        assert_eq!(kinds, vec![vec![EventKind::Window], vec![EventKind::Mode]]);
+    }
+}
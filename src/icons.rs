@@ -1,4 +1,8 @@
 use std::collections::HashMap as Map;
+use std::fs::File;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use toml::{self, value};
 
 lazy_static! {
     pub static ref NONE: Map<String, String> = map_to_owned! {
@@ -9,6 +13,9 @@ lazy_static! {
         "music_pause" => "||",
         "music_next" => " > ",
         "music_prev" => " < ",
+        "music_shuffle" => " S ",
+        "music_loop" => " L ",
+        "music_loop_track" => " L1 ",
         "cogs" => " LOAD ",
         "memory_mem" => " MEM ",
         "memory_swap" => " SWAP ",
@@ -25,6 +32,12 @@ lazy_static! {
         "volume_empty" => " VOL ",
         // This icon has no spaces around it because it is manually set as text. (sound.rs)
         "volume_muted" => "MUTED",
+        "microphone_full" => " MIC ",
+        "microphone_half" => " MIC ",
+        "microphone_empty" => " MIC ",
+        // Set as text like volume_muted
+        "microphone_muted" => "MUTED",
+        "headphones" => " HEAD ",
         "thermometer" => " TEMP ",
         "xrandr" => " SCREEN ",
         "net_up" => " UP ",
@@ -46,7 +59,12 @@ lazy_static! {
         "weather_default" => " WEATHER ",
         "uptime" => " UP ",
         "gpu" => " GPU ",
-        "mail" => " "
+        "mail" => " ",
+        "net_other" => " NET ",
+        "moon" => " NIGHT ",
+        "sun" => " DAY ",
+        "horizontal" => " H ",
+        "vertical" => " V "
     };
 
     pub static ref AWESOME: Map<String, String> = map_to_owned! {
@@ -57,6 +75,9 @@ lazy_static! {
         "music_pause" => "  \u{f04c}  ",
         "music_next" => " \u{f050} ",
         "music_prev" => " \u{f049} ",
+        "music_shuffle" => " \u{f074} ",
+        "music_loop" => " \u{f01e} ",
+        "music_loop_track" => " \u{f01e}1 ",
         "cogs" => " \u{f085} ",
         "memory_mem" => " \u{f2db} ",
         "memory_swap" => " \u{f0a0} ",
@@ -73,6 +94,12 @@ lazy_static! {
         "volume_empty" => " \u{f026} ",
         // This icon has no spaces around it because it is manually set as text. (sound.rs)
         "volume_muted" => "\u{f00d}",
+        "microphone_full" => " \u{f130} ",
+        "microphone_half" => " \u{f130} ",
+        "microphone_empty" => " \u{f130} ",
+        // Set as text like volume_muted
+        "microphone_muted" => "\u{f131}",
+        "headphones" => " \u{f025} ",
         "thermometer" => " \u{f2c8} ",
         "xrandr" => " \u{f26c} ",
         "net_up" => " \u{2b06} ",
@@ -80,6 +107,7 @@ lazy_static! {
         "net_wireless" => " \u{f1eb} ",
         "net_wired" => " \u{f0ac} ",
         "net_vpn" => " \u{f023} ",
+        "net_other" => " \u{f0c1} ",
         "ping" => " \u{21ba} ",
         "backlight_empty" => " \u{1f315} ",
         "backlight_partial1" => " \u{1f314} ",
@@ -111,6 +139,9 @@ lazy_static! {
         "music_pause" => "  \u{e034}  ",
         "music_next" => " \u{e044} ",
         "music_prev" => " \u{e045} ",
+        "music_shuffle" => " \u{e043} ",
+        "music_loop" => " \u{e040} ",
+        "music_loop_track" => " \u{e041} ",
         "cogs" => " \u{e8b8} ",
         "memory_mem" => " \u{e322} ",
         "memory_swap" => " \u{e8d4} ",
@@ -127,24 +158,179 @@ lazy_static! {
         "volume_empty" => " \u{e04e} ",
         // This icon has no spaces around it because it is manually set as text. (sound.rs)
         "volume_muted" => "\u{e04f}",
+        "microphone_full" => " \u{e029} ",
+        "microphone_half" => " \u{e029} ",
+        "microphone_empty" => " \u{e02a} ",
+        // Set as text like volume_muted
+        "microphone_muted" => "\u{e02b}",
+        "headphones" => " \u{e310} ",
         "thermometer" => " \u{f2c8} ", // TODO
         "xrandr" => " \u{e31e} ",
+        "net_up" => " \u{e5d8} ",
+        "net_down" => " \u{e5db} ",
+        "net_wireless" => " \u{e63e} ",
+        "net_wired" => " \u{e8be} ",
+        "net_vpn" => " \u{e0da} ",
+        "net_other" => " \u{e157} ",
+        "ping" => " \u{e8d5} ",
+        "backlight_empty" => " \u{e1ad} ",
+        "backlight_partial1" => " \u{e1ad} ",
+        "backlight_partial2" => " \u{e1ae} ",
+        "backlight_partial3" => " \u{e1ae} ",
+        "backlight_full" => " \u{e1ac} ",
+        "weather_sun" => " \u{e430} ",
+        "weather_snow" => " \u{eb3b} ",
+        "weather_thunder" => " \u{e3e7} ",
+        "weather_clouds" => " \u{e2bd} ",
+        "weather_rain" => " \u{e91c} ",
+        // Cloud symbol as default
+        "weather_default" => " \u{e2bd} ",
         // Same as time symbol.
         "uptime" => " \u{e192} ",
         "gpu" => " \u{e333} ",
-        "mail" => " \u{e0be} "
+        "mail" => " \u{e0be} ",
+        "moon" => " \u{e3a8} ",
+        "sun" => " \u{e430} ",
+        "horizontal" => " \u{e5d3} ",
+        "vertical" => " \u{e5d4} "
+    };
+
+    // Nerd Fonts bundle Font Awesome at its original code points, next to
+    // Material Design and Weather icons.
+    pub static ref NERD_FONTS: Map<String, String> = map_to_owned! {
+        "" => "",
+        "time" => " \u{f017} ",
+        "music" => " \u{f001} ",
+        "music_play" => "  \u{f04b}  ",
+        "music_pause" => "  \u{f04c}  ",
+        "music_next" => " \u{f051} ",
+        "music_prev" => " \u{f048} ",
+        "music_shuffle" => " \u{f074} ",
+        "music_loop" => " \u{f01e} ",
+        "music_loop_track" => " \u{f01e}1 ",
+        "cogs" => " \u{f085} ",
+        "memory_mem" => " \u{f85a} ",
+        "memory_swap" => " \u{f0a0} ",
+        "cpu" => " \u{f2db} ",
+        "bat" => " \u{f242} ",
+        "bat_full" => " \u{f240} ",
+        "bat_charging" => " \u{f583} ",
+        "bat_discharging" => " \u{f242} ",
+        "update" => " \u{f062} ",
+        "toggle_off" => " \u{f204} ",
+        "toggle_on" => " \u{f205} ",
+        "volume_full" => " \u{f028} ",
+        "volume_half" => " \u{f027} ",
+        "volume_empty" => " \u{f026} ",
+        // This icon has no spaces around it because it is manually set as text. (sound.rs)
+        "volume_muted" => "\u{f00d}",
+        "microphone_full" => " \u{f130} ",
+        "microphone_half" => " \u{f130} ",
+        "microphone_empty" => " \u{f130} ",
+        // Set as text like volume_muted
+        "microphone_muted" => "\u{f131}",
+        "headphones" => " \u{f025} ",
+        "thermometer" => " \u{f2c9} ",
+        "xrandr" => " \u{f26c} ",
+        "net_up" => " \u{f0aa} ",
+        "net_down" => " \u{f0ab} ",
+        "net_wireless" => " \u{f1eb} ",
+        "net_wired" => " \u{f6ff} ",
+        "net_vpn" => " \u{f023} ",
+        "net_other" => " \u{f0c1} ",
+        "ping" => " \u{21ba} ",
+        "backlight_empty" => " \u{1f315} ",
+        "backlight_partial1" => " \u{1f314} ",
+        "backlight_partial2" => " \u{1f313} ",
+        "backlight_partial3" => " \u{1f312} ",
+        "backlight_full" => " \u{1f311} ",
+        "weather_sun" => " \u{e30d} ",
+        "weather_snow" => " \u{e31a} ",
+        "weather_thunder" => " \u{e31d} ",
+        "weather_clouds" => " \u{e312} ",
+        "weather_rain" => " \u{e318} ",
+        "weather_default" => " \u{e312} ",
+        "uptime" => " \u{f017} ",
+        "gpu" => " \u{f26c} ",
+        "mail" => " \u{f0e0} ",
+        "moon" => " \u{f186} ",
+        "sun" => " \u{f185} ",
+        "horizontal" => " \u{f141} ",
+        "vertical" => "  \u{f142}  "
     };
 }
 
-pub fn get_icons(name: &str) -> Option<Map<String, String>> {
+/// How deep `base` references of icon files may be nested, to catch cycles.
+const MAX_BASE_DEPTH: usize = 8;
+
+fn get_builtin_icons(name: &str) -> Option<Map<String, String>> {
     match name {
         "material" => Some(MATERIAL.clone()),
         "awesome" => Some(AWESOME.clone()),
+        "nerd-fonts" => Some(NERD_FONTS.clone()),
         "none" => Some(NONE.clone()),
         _ => None,
     }
 }
 
+/// Returns the icon set called `name`, which is either one of the built-in
+/// sets or the path of a TOML file.
+pub fn get_icons(name: &str) -> Result<Map<String, String>, String> {
+    get_icons_relative_to(name, None, 0)
+}
+
+fn get_icons_relative_to(name: &str, dir: Option<&Path>, depth: usize) -> Result<Map<String, String>, String> {
+    if let Some(icons) = get_builtin_icons(name) {
+        return Ok(icons);
+    }
+    if !name.ends_with(".toml") {
+        return Err(format!("cannot find icon set '{}'", name));
+    }
+    if depth > MAX_BASE_DEPTH {
+        return Err(format!("icon set '{}' is nested too deeply, is there a cycle of `base` sets?", name));
+    }
+
+    let path = match dir {
+        Some(dir) => dir.join(name),
+        None => PathBuf::from(name),
+    };
+    load_icons_file(&path, depth)
+}
+
+/// Icon files map icon names to icons. The optional `base` key names a
+/// built-in set or another file (relative to this one) to inherit from:
+///
+/// ```toml
+/// base = "awesome"
+/// time = " T "
+/// ```
+fn load_icons_file(path: &Path, depth: usize) -> Result<Map<String, String>, String> {
+    let mut contents = String::new();
+    File::open(path)
+        .and_then(|mut file| file.read_to_string(&mut contents))
+        .map_err(|e| format!("failed to read icon file '{}': {}", path.display(), e))?;
+    let mut table: value::Table = toml::from_str(&contents)
+        .map_err(|e| format!("failed to parse icon file '{}': {}", path.display(), e))?;
+
+    let mut icons = match table.remove("base") {
+        Some(value::Value::String(base)) => get_icons_relative_to(&base, path.parent(), depth + 1)?,
+        Some(_) => return Err(format!("icon file '{}': `base` has to be a string", path.display())),
+        None => Map::new(),
+    };
+    icons.entry(String::new()).or_insert_with(String::new);
+
+    for (name, icon) in table {
+        match icon {
+            value::Value::String(icon) => {
+                icons.insert(name, icon);
+            }
+            _ => return Err(format!("icon file '{}': icon '{}' has to be a string", path.display(), name)),
+        }
+    }
+
+    Ok(icons)
+}
+
 pub fn default() -> Map<String, String> {
     NONE.clone()
 }
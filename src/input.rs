@@ -1,11 +1,12 @@
-use serde::{de, Deserializer};
+use serde::{de, Deserializer, Serializer};
 use serde_json;
 use std::fmt;
-use std::io;
+use std::io::BufRead;
 use std::option::Option;
 use std::string::*;
 use chan::Sender;
-use std::thread;
+use errors::ResultExtInternal;
+use logging::spawn_thread;
 
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub enum MouseButton {
@@ -19,15 +20,19 @@ pub enum MouseButton {
     Unknown,
 }
 
-#[derive(Deserialize, Debug, Clone)]
+#[derive(Deserialize, Serialize, Debug, Clone)]
 pub struct I3BarEvent {
     pub name: Option<String>,
     pub instance: Option<String>,
     pub x: u64,
     pub y: u64,
 
-    #[serde(deserialize_with = "deserialize_mousebutton")]
+    #[serde(deserialize_with = "deserialize_mousebutton", serialize_with = "serialize_mousebutton")]
     pub button: MouseButton,
+
+    /// Modifier keys held while clicking, like `Shift`. Only sent by i3 4.19 and later.
+    #[serde(default)]
+    pub modifiers: Vec<String>,
 }
 
 impl I3BarEvent {
@@ -37,19 +42,44 @@ impl I3BarEvent {
             _ => false,
         }
     }
+
+    pub fn has_modifier(&self, modifier: &str) -> bool {
+        self.modifiers.iter().any(|m| m == modifier)
+    }
 }
 
-pub fn process_events(sender: Sender<I3BarEvent>) {
-    thread::spawn(move || loop {
-        let mut input = String::new();
-        io::stdin().read_line(&mut input).unwrap();
+/// Parses one line of the click event stream, which is an endless JSON array
+/// written one event per line.
+pub fn parse_event(line: &str) -> Option<I3BarEvent> {
+    // Take only the valid JSON object betweem curly braces (cut off leading bracket, commas and whitespace)
+    let slice = line.trim_left_matches(|c| c != '{');
+    let slice = slice.trim_right_matches(|c| c != '}');
+
+    if slice.is_empty() {
+        return None;
+    }
+    match serde_json::from_str(slice) {
+        Ok(event) => Some(event),
+        Err(e) => {
+            warn!("input", "ignoring invalid click event {}: {}", slice, e);
+            None
+        }
+    }
+}
 
-        // Take only the valid JSON object betweem curly braces (cut off leading bracket, commas and whitespace)
-        let slice = input.trim_left_matches(|c| c != '{');
-        let slice = slice.trim_right_matches(|c| c != '}');
+/// Reads click events from `input` (usually stdin) until it is closed, which
+/// closes the channel behind `sender` as well.
+pub fn process_events<R: BufRead + Send + 'static>(mut input: R, sender: Sender<I3BarEvent>) {
+    spawn_thread("input", move || loop {
+        let mut line = String::new();
+        if input
+            .read_line(&mut line)
+            .internal_error("input", "failed to read click events")? == 0
+        {
+            return Ok(());
+        }
 
-        if !slice.is_empty() {
-            let e: I3BarEvent = serde_json::from_str(slice).unwrap();
+        if let Some(e) = parse_event(&line) {
             sender.send(e);
         }
     });
@@ -72,7 +102,7 @@ where
         where
             E: de::Error,
         {
-            eprintln!("{}", value);
+            trace!("input", "mouse button {}", value);
             Ok(match value {
                 1 => MouseButton::Left,
                 2 => MouseButton::Middle,
@@ -88,3 +118,19 @@ where
 
     deserializer.deserialize_any(MouseButtonVisitor)
 }
+
+fn serialize_mousebutton<S>(button: &MouseButton, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    serializer.serialize_u64(match *button {
+        MouseButton::Left => 1,
+        MouseButton::Middle => 2,
+        MouseButton::Right => 3,
+        MouseButton::WheelUp => 4,
+        MouseButton::WheelDown => 5,
+        MouseButton::Forward => 9,
+        MouseButton::Back => 8,
+        MouseButton::Unknown => 0,
+    })
+}
@@ -0,0 +1,51 @@
+//! What the listeners on connections to other services (D-Bus, i3 IPC) have
+//! in common: they survive the service going away and reconnect with an
+//! increasing delay, while their blocks show that their data is not current.
+
+use std::cmp;
+use std::time::Duration;
+
+/// The text blocks show while their listener is disconnected.
+pub const DISCONNECTED: &str = "disconnected";
+
+/// Seconds to wait before the first attempt to reconnect, and at most.
+const INITIAL_BACKOFF: u64 = 1;
+const MAX_BACKOFF: u64 = 60;
+
+/// Delays between attempts to reconnect, doubling up to a maximum.
+pub struct Backoff {
+    next: Duration,
+}
+
+impl Backoff {
+    pub fn new() -> Self {
+        Backoff {
+            next: Duration::from_secs(INITIAL_BACKOFF),
+        }
+    }
+
+    pub fn next(&mut self) -> Duration {
+        let delay = self.next;
+        self.next = cmp::min(self.next * 2, Duration::from_secs(MAX_BACKOFF));
+        delay
+    }
+
+    pub fn reset(&mut self) {
+        self.next = Duration::from_secs(INITIAL_BACKOFF);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reconnect_backoff_doubles_up_to_a_minute() {
+        let mut backoff = Backoff::new();
+        let delays: Vec<u64> = (0..8).map(|_| backoff.next().as_secs()).collect();
+        assert_eq!(delays, vec![1, 2, 4, 8, 16, 32, 60, 60]);
+
+        backoff.reset();
+        assert_eq!(backoff.next(), Duration::from_secs(1));
+    }
+}
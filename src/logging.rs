@@ -0,0 +1,229 @@
+//! Leveled logging with per-target filters. Targets are block names, or the
+//! name of the subsystem logging (`input`, `config`, ...).
+//!
+//! Messages go to stderr by default, or to a file or the systemd journal.
+
+use chrono::Local;
+use errors::*;
+use std::collections::HashMap;
+use std::fmt;
+use std::fs::{File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::net::UnixDatagram;
+use std::str::FromStr;
+use std::sync::Mutex;
+use std::thread;
+
+const JOURNAL_SOCKET: &str = "/run/systemd/journal/socket";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Error,
+    Warn,
+    Info,
+    Debug,
+    Trace,
+}
+
+impl Level {
+    fn name(self) -> &'static str {
+        match self {
+            Level::Error => "ERROR",
+            Level::Warn => "WARN",
+            Level::Info => "INFO",
+            Level::Debug => "DEBUG",
+            Level::Trace => "TRACE",
+        }
+    }
+
+    /// The syslog priority the journal files the message under.
+    fn priority(self) -> u8 {
+        match self {
+            Level::Error => 3,
+            Level::Warn => 4,
+            Level::Info => 6,
+            Level::Debug | Level::Trace => 7,
+        }
+    }
+}
+
+impl FromStr for Level {
+    type Err = String;
+
+    fn from_str(s: &str) -> ::std::result::Result<Self, Self::Err> {
+        match s {
+            "error" => Ok(Level::Error),
+            "warn" | "warning" => Ok(Level::Warn),
+            "info" => Ok(Level::Info),
+            "debug" => Ok(Level::Debug),
+            "trace" => Ok(Level::Trace),
+            _ => Err(format!("unknown log level '{}'", s)),
+        }
+    }
+}
+
+pub enum Output {
+    Stderr,
+    File(File),
+    Journal(UnixDatagram),
+}
+
+impl Output {
+    /// Picks the output from the command line options, stderr if none was given.
+    pub fn open(file: Option<&str>, journal: bool) -> Result<Self> {
+        if journal {
+            let socket = UnixDatagram::unbound().configuration_error("failed to create journal socket")?;
+            socket
+                .connect(JOURNAL_SOCKET)
+                .configuration_error("failed to connect to the journal")?;
+            return Ok(Output::Journal(socket));
+        }
+
+        match file {
+            Some(path) => Ok(Output::File(OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .configuration_error(&format!("failed to open log file {}", path))?)),
+            None => Ok(Output::Stderr),
+        }
+    }
+}
+
+struct Logger {
+    level: Level,
+    targets: HashMap<String, Level>,
+    output: Output,
+}
+
+impl Logger {
+    fn enabled(&self, target: &str, level: Level) -> bool {
+        level <= *self.targets.get(target).unwrap_or(&self.level)
+    }
+
+    fn write(&mut self, target: &str, level: Level, args: fmt::Arguments) {
+        // There is nowhere left to report failing to log to, so those are dropped
+        let _ = match self.output {
+            Output::Stderr => write_line(&mut io::stderr(), target, level, args),
+            Output::File(ref mut file) => write_line(file, target, level, args),
+            Output::Journal(ref socket) => {
+                // Newlines would start a new field of the entry
+                let message = format!("{}", args).replace('\n', " ");
+                let entry = format!(
+                    "PRIORITY={}\nSYSLOG_IDENTIFIER=i3status-rs\nI3RS_TARGET={}\nMESSAGE={}\n",
+                    level.priority(),
+                    target,
+                    message
+                );
+                socket.send(entry.as_bytes()).map(|_| ())
+            }
+        };
+    }
+}
+
+fn write_line<W: Write>(out: &mut W, target: &str, level: Level, args: fmt::Arguments) -> io::Result<()> {
+    writeln!(
+        out,
+        "{} {:5} [{}] {}",
+        Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+        level.name(),
+        target,
+        args
+    )
+}
+
+lazy_static! {
+    static ref LOGGER: Mutex<Logger> = Mutex::new(Logger {
+        level: Level::Warn,
+        targets: HashMap::new(),
+        output: Output::Stderr,
+    });
+}
+
+/// Parses level specifications like `info` or `sound=debug,net=trace` into the
+/// default level and the levels of single targets.
+fn parse_specs(specs: &[&str]) -> Result<(Option<Level>, HashMap<String, Level>)> {
+    let mut level = None;
+    let mut targets = HashMap::new();
+    for spec in specs.iter().flat_map(|spec| spec.split(',')) {
+        let spec = spec.trim();
+        if spec.is_empty() {
+            continue;
+        }
+        let mut parts = spec.splitn(2, '=');
+        match (parts.next(), parts.next()) {
+            (Some(target), Some(target_level)) => {
+                targets.insert(target.to_owned(), target_level.parse().configuration_error("invalid --log-level")?);
+            }
+            (Some(default_level), None) => level = Some(default_level.parse().configuration_error("invalid --log-level")?),
+            _ => unreachable!(),
+        }
+    }
+    Ok((level, targets))
+}
+
+/// Sets the levels from `--log-level` specifications and where messages go.
+pub fn init(specs: &[&str], output: Output) -> Result<()> {
+    let (level, targets) = parse_specs(specs)?;
+
+    let mut logger = LOGGER.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(level) = level {
+        logger.level = level;
+    }
+    logger.targets = targets;
+    logger.output = output;
+    Ok(())
+}
+
+pub fn log(target: &str, level: Level, args: fmt::Arguments) {
+    // A thread panicking while logging must not silence everybody else
+    let mut logger = LOGGER.lock().unwrap_or_else(|e| e.into_inner());
+    if logger.enabled(target, level) {
+        logger.write(target, level, args);
+    }
+}
+
+/// Writes `text` line by line under `target`, whatever the configured levels.
+pub fn dump(target: &str, text: &str) {
+    let mut logger = LOGGER.lock().unwrap_or_else(|e| e.into_inner());
+    for line in text.lines() {
+        logger.write(target, Level::Info, format_args!("{}", line));
+    }
+}
+
+/// Runs `f` in a new thread, logging the error it fails with under `target`
+/// rather than taking the thread down with a panic.
+pub fn spawn_thread<F>(target: &'static str, f: F)
+where
+    F: FnOnce() -> Result<()> + Send + 'static,
+{
+    thread::spawn(move || if let Err(error) = f() {
+        log(target, Level::Error, format_args!("background thread failed: {:?}", error));
+    });
+}
+
+macro_rules! log {
+    ($level:expr, $target:expr, $($arg:tt)+) => {
+        ::logging::log($target, $level, format_args!($($arg)+))
+    };
+}
+
+macro_rules! error {
+    ($target:expr, $($arg:tt)+) => { log!(::logging::Level::Error, $target, $($arg)+) };
+}
+
+macro_rules! warn {
+    ($target:expr, $($arg:tt)+) => { log!(::logging::Level::Warn, $target, $($arg)+) };
+}
+
+macro_rules! info {
+    ($target:expr, $($arg:tt)+) => { log!(::logging::Level::Info, $target, $($arg)+) };
+}
+
+macro_rules! debug {
+    ($target:expr, $($arg:tt)+) => { log!(::logging::Level::Debug, $target, $($arg)+) };
+}
+
+macro_rules! trace {
+    ($target:expr, $($arg:tt)+) => { log!(::logging::Level::Trace, $target, $($arg)+) };
+}
@@ -18,18 +18,33 @@ extern crate chrono;
 extern crate chrono_tz;
 #[cfg(feature = "pulseaudio")]
 extern crate libpulse_binding as pulse;
+#[cfg(feature = "native-alsa")]
+extern crate alsa;
 
+#[macro_use]
+mod logging;
 #[macro_use]
 mod de;
 #[macro_use]
 mod util;
 mod block;
+mod bar;
 pub mod blocks;
+mod color;
 mod config;
+mod dbus_dispatcher;
 mod errors;
+#[cfg(test)]
+mod harness;
+mod i3_dispatcher;
 mod input;
+mod listener;
+mod recording;
 mod icons;
+mod signals;
+mod stats;
 mod themes;
+mod theme_switcher;
 mod scheduler;
 mod widget;
 mod widgets;
@@ -41,17 +56,24 @@ use cpuprofiler::PROFILER;
 #[cfg(feature = "profiling")]
 extern crate progress;
 
-use std::collections::HashMap;
+use std::io::{self, BufReader};
 use std::time::Duration;
+#[cfg(feature = "profiling")]
 use std::ops::DerefMut;
 
+use bar::{create_blocks, Bar, BarEvent};
+#[cfg(feature = "profiling")]
 use block::Block;
-
+#[cfg(feature = "profiling")]
 use blocks::create_block;
 use config::Config;
 use errors::*;
 use input::{process_events, I3BarEvent};
-use scheduler::{Task, UpdateScheduler};
+use recording::{replay_events, Recording, RecordingWriter};
+use scheduler::{SystemClock, Task};
+use signals::{process_signals, Signal};
+use theme_switcher::{spawn_theme_switchers, ThemeRequest, ThemeSwitcher};
+use themes::SharedTheme;
 use widget::{I3BarWidget, State};
 use widgets::text::TextWidget;
 
@@ -82,6 +104,49 @@ fn main() {
                 )
                 .long("exit-on-error")
                 .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("record")
+                .value_name("FILE")
+                .help("records the emitted blocks and the click events to a file")
+                .long("record")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("replay")
+                .value_name("FILE")
+                .help("plays back the click events of a recording instead of reading them from stdin")
+                .long("replay")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("stats")
+                .help("dumps statistics of the blocks' updates to the log on SIGUSR1")
+                .long("stats")
+                .takes_value(false),
+        )
+        .arg(
+            Arg::with_name("log-level")
+                .value_name("SPEC")
+                .help("sets the log level, for all blocks (`info`) or single ones (`sound=debug`)")
+                .long("log-level")
+                .takes_value(true)
+                .multiple(true)
+                .number_of_values(1),
+        )
+        .arg(
+            Arg::with_name("log-file")
+                .value_name("FILE")
+                .help("appends log messages to a file rather than writing them to stderr")
+                .long("log-file")
+                .takes_value(true),
+        )
+        .arg(
+            Arg::with_name("log-journal")
+                .help("sends log messages to the systemd journal")
+                .long("log-journal")
+                .takes_value(false)
+                .conflicts_with("log-file"),
         );
 
     if_debug!({
@@ -107,7 +172,7 @@ fn main() {
     // Run and match for potential error
     if let Err(error) = run(&matches) {
         if exit_on_error {
-            eprintln!("{:?}", error);
+            error!("main", "{:?}", error);
             ::std::process::exit(1);
         }
 
@@ -120,7 +185,7 @@ fn main() {
             serde_json::to_string(&[error_rendered]).expect("failed to serialize error message")
         );
 
-        eprintln!("\n\n{:?}", error);
+        error!("main", "{:?}", error);
         // Do nothing, so the error message keeps displayed
         loop {
             ::std::thread::sleep(Duration::from_secs(::std::u64::MAX));
@@ -130,11 +195,19 @@ fn main() {
 
 #[allow(unused_mut)] // TODO: Remove when fixed in chan_select
 fn run(matches: &ArgMatches) -> Result<()> {
+    let log_levels: Vec<&str> = matches.values_of("log-level").map(|v| v.collect()).unwrap_or_default();
+    logging::init(
+        &log_levels,
+        logging::Output::open(matches.value_of("log-file"), matches.is_present("log-journal"))?,
+    )?;
+
     // Now we can start to run the i3bar protocol
     print!("{{\"version\": 1, \"click_events\": true}}\n[");
 
     // Read & parse the config file
-    let config: Config = deserialize_file(matches.value_of("config").unwrap())?;
+    let mut config: Config = deserialize_file(matches.value_of("config").unwrap())?;
+    config.resolve_roots(|name| ::std::env::var_os(name));
+    info!("config", "loaded {} blocks", config.blocks.len());
 
     // Update request channel
     let (tx_update_requests, rx_update_requests): (Sender<Task>, Receiver<Task>) = chan::async();
@@ -145,66 +218,36 @@ fn run(matches: &ArgMatches) -> Result<()> {
         return Ok(());
     }
 
-    let mut config_alternating_tint = config.clone();
-    {
-        let tint_bg = &config.theme.alternating_tint_bg;
-        config_alternating_tint.theme.idle_bg = util::add_colors(&config_alternating_tint.theme.idle_bg, tint_bg)
-            .configuration_error("can't parse alternative_tint color code")?;
-        config_alternating_tint.theme.info_bg = util::add_colors(&config_alternating_tint.theme.info_bg, tint_bg)
-            .configuration_error("can't parse alternative_tint color code")?;
-        config_alternating_tint.theme.good_bg = util::add_colors(&config_alternating_tint.theme.good_bg, tint_bg)
-            .configuration_error("can't parse alternative_tint color code")?;
-        config_alternating_tint.theme.warning_bg = util::add_colors(&config_alternating_tint.theme.warning_bg, tint_bg)
-            .configuration_error("can't parse alternative_tint color code")?;
-        config_alternating_tint.theme.critical_bg = util::add_colors(&config_alternating_tint.theme.critical_bg, tint_bg)
-            .configuration_error("can't parse alternative_tint color code")?;
-
-        let tint_fg = &config.theme.alternating_tint_fg;
-        config_alternating_tint.theme.idle_fg = util::add_colors(&config_alternating_tint.theme.idle_fg, tint_fg)
-            .configuration_error("can't parse alternative_tint color code")?;
-        config_alternating_tint.theme.info_fg = util::add_colors(&config_alternating_tint.theme.info_fg, tint_fg)
-            .configuration_error("can't parse alternative_tint color code")?;
-        config_alternating_tint.theme.good_fg = util::add_colors(&config_alternating_tint.theme.good_fg, tint_fg)
-            .configuration_error("can't parse alternative_tint color code")?;
-        config_alternating_tint.theme.warning_fg = util::add_colors(&config_alternating_tint.theme.warning_fg, tint_fg)
-            .configuration_error("can't parse alternative_tint color code")?;
-        config_alternating_tint.theme.critical_fg = util::add_colors(&config_alternating_tint.theme.critical_fg, tint_fg)
-            .configuration_error("can't parse alternative_tint color code")?;
-    }
-
-    let mut blocks: Vec<Box<Block>> = Vec::new();
-
-    let mut alternator = false;
-    // Initialize the blocks
-    for &(ref block_name, ref block_config) in &config.blocks {
-        blocks.push(create_block(
-            block_name,
-            block_config.clone(),
-            if alternator {
-                config_alternating_tint.clone()
-            } else {
-                config.clone()
-            },
-            tx_update_requests.clone(),
-        )?);
-        alternator = !alternator;
-    }
-
-    // We save the order of the blocks here,
-    // because they will be passed to an unordered HashMap
-    let order = blocks.iter().map(|x| String::from(x.id())).collect::<Vec<_>>();
+    // Signals have to be blocked before any other thread is spawned
+    let (tx_signals, rx_signals): (Sender<Signal>, Receiver<Signal>) = chan::async();
+    process_signals(tx_signals, matches.is_present("stats"))?;
 
-    let mut scheduler = UpdateScheduler::new(&blocks);
+    // Alternating blocks get their own theme handle, so both can be switched independently
+    let alternating_theme = SharedTheme::new(config
+        .theme
+        .get()
+        .alternating_tint()
+        .configuration_error("can't parse alternative_tint color code")?);
 
-    let mut block_map: HashMap<String, &mut Block> = HashMap::new();
+    let theme_switcher = ThemeSwitcher::new(&config, alternating_theme.clone());
+    let (tx_theme_requests, rx_theme_requests): (Sender<ThemeRequest>, Receiver<ThemeRequest>) = chan::async();
+    spawn_theme_switchers(&config.theme_switch, tx_theme_requests)?;
 
-    for block in &mut blocks {
-        block_map.insert(String::from(block.id()), (*block).deref_mut());
-    }
+    // Initialize the blocks
+    let mut blocks = create_blocks(&config, &alternating_theme, &tx_update_requests)?;
+    let mut bar = Bar::new(&mut blocks, &config, theme_switcher, Box::new(SystemClock)).with_stats_dump(matches.is_present("stats"));
+    let recording = match matches.value_of("record") {
+        Some(path) => Some(Recording::create(path)?),
+        None => None,
+    };
+    let mut out = RecordingWriter::new(io::stdout(), recording);
 
     // We wait for click events in a separate thread, to avoid blocking to wait for stdin
     let (tx_clicks, rx_clicks): (Sender<I3BarEvent>, Receiver<I3BarEvent>) = chan::async();
-    process_events(tx_clicks);
+    match matches.value_of("replay") {
+        Some(path) => replay_events(path, tx_clicks)?,
+        None => process_events(BufReader::new(io::stdin()), tx_clicks),
+    }
 
     // Time to next update channel.
     // Fires immediately for first updates
@@ -216,32 +259,34 @@ fn run(matches: &ArgMatches) -> Result<()> {
 
         chan_select! {
             // Receive click events
-            rx_clicks.recv() -> res => if let Some(event) = res {
-                    for block in block_map.values_mut() {
-                        block.click(&event)?;
-                    }
-                    util::print_blocks(&order, &block_map, &config)?;
+            rx_clicks.recv() -> res => match res {
+                Some(event) => {
+                    out.record_click(&event)?;
+                    bar.handle(BarEvent::Click(event), &mut out)?;
+                },
+                // i3bar closed our stdin (or the replay is over), so nobody is listening anymore
+                None => return Ok(()),
             },
             // Receive async update requests
             rx_update_requests.recv() -> res => if let Some(request) = res {
-                // Process immediately and forget
-                block_map
-                    .get_mut(&request.id)
-                    .internal_error("scheduler", "could not get required block")?
-                    .update()?;
-                util::print_blocks(&order, &block_map, &config)?;
+                bar.handle(BarEvent::UpdateRequest(request), &mut out)?;
+            },
+            // Receive theme switch requests
+            rx_theme_requests.recv() -> res => if let Some(request) = res {
+                bar.handle(BarEvent::ThemeRequest(request), &mut out)?;
+            },
+            // Receive signals
+            rx_signals.recv() -> res => if let Some(signal) = res {
+                bar.handle(BarEvent::Signal(signal), &mut out)?;
             },
             // Receive update timer events
             ttnu.recv() => {
-                scheduler.do_scheduled_updates(&mut block_map)?;
-
-                // redraw the blocks, state changed
-                util::print_blocks(&order, &block_map, &config)?;
+                bar.handle(BarEvent::ScheduledUpdate, &mut out)?;
             }
         }
 
         // Set the time-to-next-update timer
-        match scheduler.time_to_next_update() {
+        match bar.time_to_next_update() {
             Some(time) => ttnu = chan::after(time),
             None => ttnu = chan::after(Duration::from_secs(std::u64::MAX)),
         }
@@ -278,7 +323,7 @@ fn profile(iterations: i32, name: &str, block: &mut Block) {
 fn profile_config(name: &str, runs: &str, config: &Config, update: Sender<Task>) -> Result<()> {
     let profile_runs = runs.parse::<i32>()
         .configuration_error("failed to parse --profile-runs as an integer")?;
-    for &(ref block_name, ref block_config) in &config.blocks {
+    for &(ref block_name, _, ref block_config) in &config.blocks {
         if block_name == name {
             let mut block = create_block(
                 &block_name,
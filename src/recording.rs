@@ -0,0 +1,170 @@
+//! Recording of the frames written and the clicks received, one JSON object
+//! per line, and playing back the clicks of such a recording:
+//!
+//! ```json
+//! {"time":0.004,"frame":[{"full_text":" CPU 05% ", ...}]}
+//! {"time":2.301,"click":{"name":"memory","instance":null,"x":1822,"y":10,"button":1}}
+//! ```
+//!
+//! `time` is the number of seconds since the bar was started.
+
+use chan::Sender;
+use errors::*;
+use input::I3BarEvent;
+use logging::spawn_thread;
+use serde_json::{self, Value};
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, LineWriter, Write};
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn seconds(duration: Duration) -> f64 {
+    duration.as_secs() as f64 + f64::from(duration.subsec_nanos()) / 1_000_000_000f64
+}
+
+pub struct Recording {
+    file: LineWriter<File>,
+    start: Instant,
+}
+
+impl Recording {
+    pub fn create(path: &str) -> Result<Self> {
+        Ok(Recording {
+            file: LineWriter::new(File::create(path).configuration_error(&format!("failed to create recording {}", path))?),
+            start: Instant::now(),
+        })
+    }
+
+    /// Writes an entry with the raw JSON `value` under `key`.
+    fn write_entry(&mut self, key: &str, value: &str) -> io::Result<()> {
+        writeln!(self.file, "{{\"time\":{:.3},\"{}\":{}}}", seconds(self.start.elapsed()), key, value)
+    }
+}
+
+/// Writes frames to `inner`, and also to a recording if there is one.
+pub struct RecordingWriter<W: Write> {
+    inner: W,
+    recording: Option<Recording>,
+    line: Vec<u8>,
+}
+
+impl<W: Write> RecordingWriter<W> {
+    pub fn new(inner: W, recording: Option<Recording>) -> Self {
+        RecordingWriter {
+            inner,
+            recording,
+            line: Vec::new(),
+        }
+    }
+
+    pub fn record_click(&mut self, event: &I3BarEvent) -> Result<()> {
+        if let Some(ref mut recording) = self.recording {
+            let click = serde_json::to_string(event).internal_error("recording", "failed to serialize click")?;
+            recording
+                .write_entry("click", &click)
+                .internal_error("recording", "failed to record click")?;
+        }
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for RecordingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        self.inner.write_all(buf)?;
+
+        if let Some(ref mut recording) = self.recording {
+            self.line.extend_from_slice(buf);
+            // Every frame is a line of its own, ending in a comma
+            while let Some(end) = self.line.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = self.line.drain(..end + 1).collect();
+                let frame = String::from_utf8_lossy(&line);
+                let frame = frame.trim_right().trim_right_matches(',');
+                if !frame.is_empty() {
+                    recording.write_entry("frame", frame)?;
+                }
+            }
+        }
+
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Reads the clicks of the recording at `path`, along with when they happened.
+pub fn read_clicks(path: &str) -> Result<Vec<(Duration, I3BarEvent)>> {
+    let file = File::open(path).configuration_error(&format!("failed to open recording {}", path))?;
+
+    let mut clicks = Vec::new();
+    for line in BufReader::new(file).lines() {
+        let line = line.configuration_error("failed to read recording")?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: Value = serde_json::from_str(&line).configuration_error("invalid line in recording")?;
+        if let Some(click) = entry.get("click") {
+            let time = entry["time"]
+                .as_f64()
+                .ok_or("missing time")
+                .configuration_error("invalid click in recording")?;
+            let event: I3BarEvent = serde_json::from_value(click.clone()).configuration_error("invalid click in recording")?;
+            clicks.push((
+                Duration::new(time as u64, (time.fract() * 1_000_000_000f64) as u32),
+                event,
+            ));
+        }
+    }
+
+    Ok(clicks)
+}
+
+/// Sends the clicks of the recording at `path` at the time they were recorded,
+/// then closes the channel behind `sender`.
+pub fn replay_events(path: &str, sender: Sender<I3BarEvent>) -> Result<()> {
+    let clicks = read_clicks(path)?;
+
+    spawn_thread("recording", move || {
+        let start = Instant::now();
+        for (time, event) in clicks {
+            let elapsed = start.elapsed();
+            if time > elapsed {
+                thread::sleep(time - elapsed);
+            }
+            sender.send(event);
+        }
+        Ok(())
+    });
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use input::{parse_event, MouseButton};
+    use std::fs;
+
+    #[test]
+    fn recordings_keep_frames_and_clicks() {
+        let path = ::std::env::temp_dir().join(format!("i3status-rs-recording-{}", ::uuid::Uuid::new_v4().simple()));
+        let path = path.to_str().expect("temporary directory is not UTF-8");
+        {
+            let mut out = RecordingWriter::new(Vec::new(), Some(Recording::create(path).expect("failed to create recording")));
+            writeln!(out, "[{{\"full_text\":\"a\"}}],").expect("failed to write frame");
+            let click = parse_event(r#"{"name":"memory","button":3,"x":1,"y":2}"#).expect("invalid click");
+            out.record_click(&click).expect("failed to record click");
+        }
+
+        let recorded = fs::read_to_string(path).expect("failed to read recording");
+        assert!(recorded.lines().next().unwrap().ends_with(r#""frame":[{"full_text":"a"}]}"#));
+
+        let clicks = read_clicks(path).expect("failed to read clicks");
+        assert_eq!(clicks.len(), 1);
+        assert_eq!(clicks[0].1.name, Some("memory".to_owned()));
+        assert_eq!(clicks[0].1.button, MouseButton::Right);
+
+        fs::remove_file(path).expect("failed to clean up recording");
+    }
+}
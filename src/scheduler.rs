@@ -1,5 +1,6 @@
 use block::Block;
 use errors::*;
+use stats;
 use std::collections::{BinaryHeap, HashMap};
 use std::fmt;
 use std::thread;
@@ -38,15 +39,34 @@ impl cmp::Ord for Task {
     }
 }
 
+/// The source of time of the scheduler, which tests replace to control time.
+pub trait Clock {
+    fn now(&self) -> Instant;
+    fn sleep(&self, duration: Duration);
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+
+    fn sleep(&self, duration: Duration) {
+        thread::sleep(duration)
+    }
+}
+
 pub struct UpdateScheduler {
     schedule: BinaryHeap<Task>,
+    clock: Box<Clock>,
 }
 
 impl UpdateScheduler {
-    pub fn new(blocks: &[Box<Block>]) -> UpdateScheduler {
+    pub fn new(blocks: &[Box<Block>], clock: Box<Clock>) -> UpdateScheduler {
         let mut schedule = BinaryHeap::new();
 
-        let now = Instant::now();
+        let now = clock.now();
         for block in blocks.iter() {
             schedule.push(Task {
                 id: String::from(block.id()),
@@ -54,13 +74,13 @@ impl UpdateScheduler {
             });
         }
 
-        UpdateScheduler { schedule }
+        UpdateScheduler { schedule, clock }
     }
 
     pub fn time_to_next_update(&self) -> Option<Duration> {
         if let Some(peeked) = self.schedule.peek() {
             let next_update = peeked.update_time;
-            let now = Instant::now();
+            let now = self.clock.now();
 
             if next_update > now {
                 Some(next_update - now)
@@ -90,19 +110,18 @@ impl UpdateScheduler {
                 .internal_error("scheduler", "schedule is empty")?)
         }
 
-        let now = Instant::now();
+        let now = self.clock.now();
         if t.update_time > now {
-            thread::sleep(t.update_time - now);
+            self.clock.sleep(t.update_time - now);
         }
 
-        let now = Instant::now();
+        let now = self.clock.now();
 
         for task in tasks_next {
-            if let Some(dur) = block_map
+            let block = block_map
                 .get_mut(&task.id)
-                .internal_error("scheduler", "could not get required block")?
-                .update()?
-            {
+                .internal_error("scheduler", "could not get required block")?;
+            if let Some(dur) = stats::record_update(&task.id, || block.update())? {
                 self.schedule.push(Task {
                     id: task.id,
                     update_time: now + dur,
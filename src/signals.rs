@@ -0,0 +1,38 @@
+use chan::Sender;
+
+use errors::*;
+use logging::spawn_thread;
+
+extern crate nix;
+pub use self::nix::sys::signal::Signal;
+use self::nix::sys::signal::SigSet;
+
+/// Signals that are delivered to the main loop instead of their default action.
+const HANDLED_SIGNALS: &[Signal] = &[Signal::SIGUSR2];
+
+/// Blocks the handled signals and waits for them in a separate thread.
+/// `SIGUSR1` is only handled with `stats_dump`, and keeps its default
+/// action otherwise.
+///
+/// This has to be called before any other thread is spawned, since only
+/// threads created afterwards inherit the signal mask.
+pub fn process_signals(sender: Sender<Signal>, stats_dump: bool) -> Result<()> {
+    let mut signals = SigSet::empty();
+    for signal in HANDLED_SIGNALS {
+        signals.add(*signal);
+    }
+    if stats_dump {
+        signals.add(Signal::SIGUSR1);
+    }
+    signals
+        .thread_block()
+        .internal_error("signals", "failed to block signals")?;
+
+    spawn_thread("signals", move || loop {
+        if let Ok(signal) = signals.wait() {
+            sender.send(signal);
+        }
+    });
+
+    Ok(())
+}
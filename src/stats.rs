@@ -0,0 +1,237 @@
+//! Lightweight runtime statistics of the blocks: how often and how long they
+//! update, how many subprocesses they spawn and how often they fail.
+//!
+//! Unlike the `profiling` feature, these are always collected. They are dumped
+//! on `SIGUSR1` when running with `--stats`.
+
+use errors::*;
+use std::cell::RefCell;
+use std::ffi::OsStr;
+use std::io;
+use std::process::{self, Child, ExitStatus, Output, Stdio};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Default, Clone)]
+pub struct BlockStats {
+    pub name: String,
+    pub updates: u64,
+    pub total_time: Duration,
+    pub max_time: Duration,
+    pub subprocesses: u64,
+    pub errors: u64,
+}
+
+impl BlockStats {
+    pub fn mean_time(&self) -> Duration {
+        if self.updates == 0 {
+            Duration::from_secs(0)
+        } else {
+            self.total_time / self.updates as u32
+        }
+    }
+}
+
+lazy_static! {
+    /// Statistics by block id, in the order the blocks were created.
+    static ref STATS: Mutex<Vec<(String, BlockStats)>> = Mutex::new(Vec::new());
+}
+
+thread_local! {
+    /// The id of the block running on this thread, which subprocesses are counted for.
+    static CURRENT: RefCell<Option<String>> = RefCell::new(None);
+}
+
+fn with_stats<F: FnOnce(&mut BlockStats)>(id: &str, f: F) {
+    let mut stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(&mut (_, ref mut block)) = stats.iter_mut().find(|&&mut (ref block_id, _)| block_id == id) {
+        f(block);
+    }
+}
+
+/// Starts collecting statistics for the block with `id`.
+pub fn register(id: &str, name: &str) {
+    let mut stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+    stats.push((
+        id.to_owned(),
+        BlockStats {
+            name: name.to_owned(),
+            ..BlockStats::default()
+        },
+    ));
+}
+
+pub fn get(id: &str) -> Option<BlockStats> {
+    let stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+    stats
+        .iter()
+        .find(|&&(ref block_id, _)| block_id == id)
+        .map(|&(_, ref block)| block.clone())
+}
+
+fn run<T, F: FnOnce() -> Result<T>>(id: &str, f: F) -> Result<T> {
+    CURRENT.with(|current| *current.borrow_mut() = Some(id.to_owned()));
+    let result = f();
+    CURRENT.with(|current| *current.borrow_mut() = None);
+
+    if result.is_err() {
+        with_stats(id, |block| block.errors += 1);
+    }
+    result
+}
+
+/// Runs the update `f` of the block with `id`, timing it.
+pub fn record_update<T, F: FnOnce() -> Result<T>>(id: &str, f: F) -> Result<T> {
+    let start = Instant::now();
+    let result = run(id, f);
+    let elapsed = start.elapsed();
+
+    with_stats(id, |block| {
+        block.updates += 1;
+        block.total_time += elapsed;
+        if elapsed > block.max_time {
+            block.max_time = elapsed;
+        }
+    });
+    result
+}
+
+/// Runs the click handler `f` of the block with `id`. Clicks are not timed,
+/// but their subprocesses and errors count.
+pub fn record_click<T, F: FnOnce() -> Result<T>>(id: &str, f: F) -> Result<T> {
+    run(id, f)
+}
+
+/// A `std::process::Command` which counts the subprocesses it starts for a
+/// block.
+pub struct Command {
+    inner: process::Command,
+    block: Option<String>,
+}
+
+impl Command {
+    /// Counts the subprocesses for the block with `id`. Needed on the block's
+    /// own threads, where no update or click is running.
+    pub fn block(&mut self, id: &str) -> &mut Command {
+        self.block = Some(id.to_owned());
+        self
+    }
+
+    pub fn arg<S: AsRef<OsStr>>(&mut self, arg: S) -> &mut Command {
+        self.inner.arg(arg);
+        self
+    }
+
+    pub fn args<I, S>(&mut self, args: I) -> &mut Command
+    where
+        I: IntoIterator<Item = S>,
+        S: AsRef<OsStr>,
+    {
+        self.inner.args(args);
+        self
+    }
+
+    pub fn env<K: AsRef<OsStr>, V: AsRef<OsStr>>(&mut self, key: K, value: V) -> &mut Command {
+        self.inner.env(key, value);
+        self
+    }
+
+    pub fn stdout<T: Into<Stdio>>(&mut self, cfg: T) -> &mut Command {
+        self.inner.stdout(cfg);
+        self
+    }
+
+    fn count(&self) {
+        if let Some(ref id) = self.block {
+            with_stats(id, |block| block.subprocesses += 1);
+        }
+    }
+
+    pub fn spawn(&mut self) -> io::Result<Child> {
+        self.count();
+        self.inner.spawn()
+    }
+
+    pub fn output(&mut self) -> io::Result<Output> {
+        self.count();
+        self.inner.output()
+    }
+
+    pub fn status(&mut self) -> io::Result<ExitStatus> {
+        self.count();
+        self.inner.status()
+    }
+}
+
+/// Creates a command like `Command::new`. Blocks spawn subprocesses through
+/// this; they are counted when started, for the block whose update or click is
+/// running on this thread unless `Command::block` names another one.
+pub fn command<S: AsRef<OsStr>>(program: S) -> Command {
+    Command {
+        inner: process::Command::new(program),
+        block: CURRENT.with(|current| current.borrow().clone()),
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs() as f64 * 1000f64 + f64::from(duration.subsec_nanos()) / 1_000_000f64
+}
+
+/// A table of the statistics of all blocks.
+pub fn report() -> String {
+    let stats = STATS.lock().unwrap_or_else(|e| e.into_inner());
+    let mut report = format!(
+        "{:<16} {:>8} {:>10} {:>10} {:>12} {:>7}\n",
+        "block",
+        "updates",
+        "mean ms",
+        "max ms",
+        "subprocesses",
+        "errors"
+    );
+    for &(_, ref block) in stats.iter() {
+        report.push_str(&format!(
+            "{:<16} {:>8} {:>10.3} {:>10.3} {:>12} {:>7}\n",
+            block.name,
+            block.updates,
+            millis(block.mean_time()),
+            millis(block.max_time),
+            block.subprocesses,
+            block.errors
+        ));
+    }
+    report
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stats_count_updates_subprocesses_and_errors() {
+        let id = ::uuid::Uuid::new_v4().simple().to_string();
+        register(&id, "test");
+        record_update(&id, || {
+            command("true").status().expect("true failed");
+            // Created but never started
+            command("true");
+            Ok(())
+        }).expect("update failed");
+        assert!(record_update(&id, || -> Result<()> { Err(BlockError("test".to_owned(), "failed".to_owned())) }).is_err());
+        record_click(&id, || Ok(())).expect("click failed");
+        // Not running any block
+        command("true").status().expect("true failed");
+        // On a thread of the block
+        let thread_id = id.clone();
+        ::std::thread::spawn(move || command("true").block(&thread_id).status().expect("true failed"))
+            .join()
+            .expect("thread panicked");
+
+        let block = get(&id).expect("block not registered");
+        assert_eq!(block.updates, 2);
+        assert_eq!(block.subprocesses, 2);
+        assert_eq!(block.errors, 1);
+        assert!(block.max_time >= block.mean_time());
+        assert!(report().contains("test"));
+    }
+}
@@ -0,0 +1,279 @@
+//! Switching between a dark and a light theme at runtime.
+//!
+//! The active theme lives in a [`SharedTheme`](../themes/struct.SharedTheme.html)
+//! handle that every widget renders from, so switching only has to replace the
+//! theme behind the handles and redraw the bar. Switch requests come from a
+//! time-of-day schedule, the desktop's `color-scheme` setting, a control FIFO or
+//! `SIGUSR2`, which toggles between both themes.
+
+use std::fs::File;
+use std::io::{BufRead, BufReader};
+use std::os::unix::fs::FileTypeExt;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use chan::Sender;
+use chrono::{self, Local, NaiveTime};
+
+use config::Config;
+use dbus_dispatcher::{self, Bus, DbusEvent, MatchRule, Message};
+use errors::*;
+use logging::spawn_thread;
+use themes::{SharedTheme, Theme};
+
+extern crate dbus;
+use self::dbus::arg::Variant;
+extern crate nix;
+use self::nix::sys::stat::Mode;
+use self::nix::unistd::mkfifo;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ThemeMode {
+    Dark,
+    Light,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ThemeRequest {
+    Set(ThemeMode),
+    Toggle,
+}
+
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ThemeSwitchConfig {
+    /// Time of day ("HH:MM") at which to switch to the light theme
+    pub light_at: Option<String>,
+
+    /// Time of day ("HH:MM") at which to switch back to the dark theme
+    pub dark_at: Option<String>,
+
+    /// Follow the `org.freedesktop.appearance` `color-scheme` setting of the desktop portal
+    #[serde(default)]
+    pub follow_desktop: bool,
+
+    /// FIFO accepting the commands `dark`, `light` and `toggle`, one per line
+    pub control_fifo: Option<String>,
+}
+
+/// Keeps track of the active theme mode and updates the theme handles of the
+/// bar on request.
+pub struct ThemeSwitcher {
+    mode: ThemeMode,
+    dark: Theme,
+    light: Theme,
+    theme: SharedTheme,
+    alternating_theme: SharedTheme,
+}
+
+impl ThemeSwitcher {
+    /// `config.theme` is used as the dark theme and is active at startup.
+    pub fn new(config: &Config, alternating_theme: SharedTheme) -> Self {
+        let dark = config.theme.get().clone();
+        ThemeSwitcher {
+            mode: ThemeMode::Dark,
+            light: config.light_theme.clone().unwrap_or_else(|| dark.clone()),
+            dark,
+            theme: config.theme.clone(),
+            alternating_theme,
+        }
+    }
+
+    /// Applies a switch request. Returns whether the bar has to be redrawn.
+    pub fn apply(&mut self, request: ThemeRequest) -> Result<bool> {
+        let mode = match request {
+            ThemeRequest::Set(mode) => mode,
+            ThemeRequest::Toggle => match self.mode {
+                ThemeMode::Dark => ThemeMode::Light,
+                ThemeMode::Light => ThemeMode::Dark,
+            },
+        };
+        if mode == self.mode {
+            return Ok(false);
+        }
+
+        let theme = match mode {
+            ThemeMode::Dark => self.dark.clone(),
+            ThemeMode::Light => self.light.clone(),
+        };
+        self.alternating_theme.set(theme
+            .alternating_tint()
+            .configuration_error("can't parse alternative_tint color code")?);
+        self.theme.set(theme);
+        self.mode = mode;
+
+        Ok(true)
+    }
+}
+
+/// Starts the configured sources of switch requests.
+pub fn spawn_theme_switchers(config: &ThemeSwitchConfig, sender: Sender<ThemeRequest>) -> Result<()> {
+    match (&config.light_at, &config.dark_at) {
+        (&Some(ref light_at), &Some(ref dark_at)) => {
+            let light_at = NaiveTime::parse_from_str(light_at, "%H:%M").configuration_error("failed to parse theme_switch.light_at")?;
+            let dark_at = NaiveTime::parse_from_str(dark_at, "%H:%M").configuration_error("failed to parse theme_switch.dark_at")?;
+            follow_schedule(light_at, dark_at, sender.clone());
+        }
+        (&None, &None) => {}
+        _ => {
+            return Err(ConfigurationError(
+                "theme_switch needs both light_at and dark_at".to_owned(),
+                (String::new(), String::new()),
+            ))
+        }
+    }
+
+    if config.follow_desktop {
+        follow_desktop(sender.clone());
+    }
+
+    if let Some(ref path) = config.control_fifo {
+        let path = PathBuf::from(path);
+        if !path.exists() {
+            mkfifo(path.as_path(), Mode::S_IRUSR | Mode::S_IWUSR).internal_error("theme_switcher", "failed to create control FIFO")?;
+        }
+        // Reading a regular file would never block, but re-apply its lines forever
+        let is_fifo = path.metadata()
+            .map(|metadata| metadata.file_type().is_fifo())
+            .configuration_error("failed to inspect theme_switch.control_fifo")?;
+        if !is_fifo {
+            return Err(ConfigurationError(
+                format!("theme_switch.control_fifo '{}' exists but is not a FIFO", path.display()),
+                (String::new(), String::new()),
+            ));
+        }
+        follow_control_fifo(path, sender);
+    }
+
+    Ok(())
+}
+
+fn scheduled_mode(now: NaiveTime, light_at: NaiveTime, dark_at: NaiveTime) -> ThemeMode {
+    let light = if light_at <= dark_at {
+        now >= light_at && now < dark_at
+    } else {
+        now >= light_at || now < dark_at
+    };
+    if light {
+        ThemeMode::Light
+    } else {
+        ThemeMode::Dark
+    }
+}
+
+fn time_until(now: NaiveTime, then: NaiveTime) -> Duration {
+    let mut delta = then.signed_duration_since(now);
+    if delta <= chrono::Duration::zero() {
+        delta = delta + chrono::Duration::days(1);
+    }
+    delta.to_std().unwrap_or_else(|_| Duration::from_secs(60))
+}
+
+fn follow_schedule(light_at: NaiveTime, dark_at: NaiveTime, sender: Sender<ThemeRequest>) {
+    spawn_thread("theme_switcher", move || loop {
+        let now = Local::now().time();
+        sender.send(ThemeRequest::Set(scheduled_mode(now, light_at, dark_at)));
+
+        // Wake up shortly after the next switch, so that it is already due.
+        let next = ::std::cmp::min(time_until(now, light_at), time_until(now, dark_at));
+        thread::sleep(next + Duration::from_secs(1));
+    });
+}
+
+/// Maps the portal's `color-scheme` value (0: no preference, 1: dark, 2: light).
+/// Without a preference, the theme is left as it is.
+fn portal_mode(color_scheme: u32) -> Option<ThemeMode> {
+    match color_scheme {
+        1 => Some(ThemeMode::Dark),
+        2 => Some(ThemeMode::Light),
+        _ => None,
+    }
+}
+
+/// Asks the portal for the desktop's current color scheme.
+fn read_color_scheme() -> Option<ThemeMode> {
+    let read = Message::new_method_call(
+        "org.freedesktop.portal.Desktop",
+        "/org/freedesktop/portal/desktop",
+        "org.freedesktop.portal.Settings",
+        "Read",
+    ).ok()?
+        .append2("org.freedesktop.appearance", "color-scheme");
+    let reply = dbus_dispatcher::call(Bus::Session, read).ok()?;
+    // The value is wrapped twice: once by the method, once by the setting itself.
+    reply
+        .get1::<Variant<Variant<u32>>>()
+        .and_then(|value| portal_mode((value.0).0))
+}
+
+fn follow_desktop(sender: Sender<ThemeRequest>) {
+    if let Some(mode) = read_color_scheme() {
+        sender.send(ThemeRequest::Set(mode));
+    }
+
+    let subscribed = dbus_dispatcher::subscribe(
+        Bus::Session,
+        MatchRule::new()
+            .with_interface("org.freedesktop.portal.Settings")
+            .with_member("SettingChanged"),
+        move |event| if let DbusEvent::Signal(signal) = event {
+            if let (Some("org.freedesktop.appearance"), Some("color-scheme"), Some(value)) = signal.get3::<&str, &str, Variant<u32>>() {
+                if let Some(mode) = portal_mode(value.0) {
+                    sender.send(ThemeRequest::Set(mode));
+                }
+            }
+        },
+    );
+    if let Err(error) = subscribed {
+        warn!("theme_switcher", "not following the desktop's color scheme: {:?}", error);
+    }
+}
+
+fn follow_control_fifo(path: PathBuf, sender: Sender<ThemeRequest>) {
+    spawn_thread("theme_switcher", move || loop {
+        // Opening blocks until a writer shows up, reading ends when it closes the FIFO.
+        let fifo = match File::open(Path::new(&path)) {
+            Ok(fifo) => fifo,
+            Err(_) => {
+                thread::sleep(Duration::from_secs(1));
+                continue;
+            }
+        };
+        for line in BufReader::new(fifo).lines().scan((), |_, x| x.ok()) {
+            match line.trim() {
+                "dark" => sender.send(ThemeRequest::Set(ThemeMode::Dark)),
+                "light" => sender.send(ThemeRequest::Set(ThemeMode::Light)),
+                "toggle" => sender.send(ThemeRequest::Toggle),
+                _ => {}
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn portal_without_preference_keeps_the_theme() {
+        assert_eq!(portal_mode(0), None);
+        assert_eq!(portal_mode(1), Some(ThemeMode::Dark));
+        assert_eq!(portal_mode(2), Some(ThemeMode::Light));
+    }
+
+    #[test]
+    fn control_fifo_must_be_a_fifo() {
+        let path = ::std::env::temp_dir().join(format!("i3status-rs-theme-{}", ::uuid::Uuid::new_v4().simple()));
+        File::create(&path).unwrap();
+        let config = ThemeSwitchConfig {
+            control_fifo: Some(path.to_string_lossy().into_owned()),
+            ..ThemeSwitchConfig::default()
+        };
+        let (tx, _rx) = ::chan::async();
+
+        let started = spawn_theme_switchers(&config, tx);
+        ::std::fs::remove_file(&path).unwrap();
+        assert!(started.is_err());
+    }
+}
@@ -1,4 +1,7 @@
 use std::str::FromStr;
+use std::sync::{Arc, RwLock, RwLockReadGuard};
+
+use color::{Color, TintMode};
 
 lazy_static! {
     pub static ref SLICK: Theme = Theme {
@@ -15,8 +18,10 @@ lazy_static! {
         separator: "\u{e0b2}".to_owned(),
         separator_bg: "auto".to_owned(),
         separator_fg: "auto".to_owned(),
+        separator_block_width: "0".to_owned(),
         alternating_tint_bg: "#111111".to_owned(),
         alternating_tint_fg: "#111111".to_owned(),
+        alternating_tint_mode: "add".to_owned(),
     };
 
     pub static ref SOLARIZED_DARK: Theme = Theme {
@@ -33,8 +38,10 @@ lazy_static! {
         separator: "\u{e0b2}".to_owned(),
         separator_bg: "auto".to_owned(),
         separator_fg: "auto".to_owned(),
+        separator_block_width: "0".to_owned(),
         alternating_tint_bg: "#11111105".to_owned(),
         alternating_tint_fg: "#000000".to_owned(),
+        alternating_tint_mode: "add".to_owned(),
     };
 
     pub static ref SOLARIZED_LIGHT: Theme = Theme {
@@ -51,8 +58,10 @@ lazy_static! {
         separator: "\u{e0b2}".to_owned(),
         separator_bg: "auto".to_owned(),
         separator_fg: "auto".to_owned(),
+        separator_block_width: "0".to_owned(),
         alternating_tint_bg: "#000000".to_owned(),
         alternating_tint_fg: "#000000".to_owned(),
+        alternating_tint_mode: "add".to_owned(),
     };
 
     pub static ref MODERN: Theme = Theme {
@@ -69,8 +78,10 @@ lazy_static! {
         separator: "\u{e0b2}".to_owned(),
         separator_bg: "auto".to_owned(),
         separator_fg: "auto".to_owned(),
+        separator_block_width: "0".to_owned(),
         alternating_tint_bg: "#000000".to_owned(),
         alternating_tint_fg: "#000000".to_owned(),
+        alternating_tint_mode: "add".to_owned(),
     };
 
     pub static ref PLAIN: Theme = Theme {
@@ -87,8 +98,10 @@ lazy_static! {
         separator: "| ".to_owned(),
         separator_bg: "#000000".to_owned(),
         separator_fg: "#a9a9a9".to_owned(),
+        separator_block_width: "0".to_owned(),
         alternating_tint_bg: "#000000".to_owned(),
         alternating_tint_fg: "#000000".to_owned(),
+        alternating_tint_mode: "add".to_owned(),
     };
 
     pub static ref BAD_WOLF: Theme = Theme {
@@ -105,8 +118,10 @@ lazy_static! {
         separator: "\u{e0b2}".to_owned(),
         separator_bg: "auto".to_owned(),
         separator_fg: "auto".to_owned(),
+        separator_block_width: "0".to_owned(),
         alternating_tint_bg: "#000000".to_owned(),
         alternating_tint_fg: "#000000".to_owned(),
+        alternating_tint_mode: "add".to_owned(),
     };
 
     pub static ref GRUVBOX_LIGHT: Theme = Theme {
@@ -123,8 +138,10 @@ lazy_static! {
         separator: "\u{e0b2}".to_owned(),
         separator_bg: "auto".to_owned(),
         separator_fg: "auto".to_owned(),
+        separator_block_width: "0".to_owned(),
         alternating_tint_bg: "#000000".to_owned(),
         alternating_tint_fg: "#000000".to_owned(),
+        alternating_tint_mode: "add".to_owned(),
     };
 
     pub static ref GRUVBOX_DARK: Theme = Theme {
@@ -141,8 +158,10 @@ lazy_static! {
         separator: "\u{e0b2}".to_owned(),
         separator_bg: "auto".to_owned(),
         separator_fg: "auto".to_owned(),
+        separator_block_width: "0".to_owned(),
         alternating_tint_bg: "#000000".to_owned(),
         alternating_tint_fg: "#000000".to_owned(),
+        alternating_tint_mode: "add".to_owned(),
     };
 }
 
@@ -163,8 +182,163 @@ mapped_struct! {
         pub separator,
         pub separator_bg,
         pub separator_fg,
+        pub separator_block_width,
         pub alternating_tint_bg,
-        pub alternating_tint_fg
+        pub alternating_tint_fg,
+        pub alternating_tint_mode
+    }
+}
+
+impl Theme {
+    /// Parses all colors of the theme and converts them to the hex notation
+    /// understood by i3bar, so that e.g. named colors can be used in themes.
+    pub fn normalized(&self) -> Result<Theme, String> {
+        let mut theme = self.clone();
+        theme.alternating_tint_mode.parse::<TintMode>()?;
+        theme
+            .separator_block_width
+            .parse::<u32>()
+            .map_err(|e| format!("invalid separator_block_width: {}", e))?;
+        for color in theme.colors_mut() {
+            *color = color.parse::<Color>()?.to_string();
+        }
+        Ok(theme)
+    }
+
+    /// Returns a copy of the theme with the alternating tint applied to all
+    /// state colors, used for every other block on the bar.
+    pub fn alternating_tint(&self) -> Result<Theme, String> {
+        let mode = self.alternating_tint_mode.parse::<TintMode>()?;
+        let tint_bg = self.alternating_tint_bg.parse::<Color>()?;
+        let tint_fg = self.alternating_tint_fg.parse::<Color>()?;
+
+        let mut theme = self.clone();
+        for bg in theme.backgrounds_mut() {
+            *bg = bg.parse::<Color>()?.tint(tint_bg, mode).to_string();
+        }
+        for fg in theme.foregrounds_mut() {
+            *fg = fg.parse::<Color>()?.tint(tint_fg, mode).to_string();
+        }
+        Ok(theme)
+    }
+
+    fn backgrounds_mut(&mut self) -> Vec<&mut String> {
+        vec![
+            &mut self.idle_bg,
+            &mut self.info_bg,
+            &mut self.good_bg,
+            &mut self.warning_bg,
+            &mut self.critical_bg,
+        ]
+    }
+
+    fn foregrounds_mut(&mut self) -> Vec<&mut String> {
+        vec![
+            &mut self.idle_fg,
+            &mut self.info_fg,
+            &mut self.good_fg,
+            &mut self.warning_fg,
+            &mut self.critical_fg,
+        ]
+    }
+
+    fn colors_mut(&mut self) -> Vec<&mut String> {
+        vec![
+            &mut self.idle_bg,
+            &mut self.idle_fg,
+            &mut self.info_bg,
+            &mut self.info_fg,
+            &mut self.good_bg,
+            &mut self.good_fg,
+            &mut self.warning_bg,
+            &mut self.warning_fg,
+            &mut self.critical_bg,
+            &mut self.critical_fg,
+            &mut self.separator_bg,
+            &mut self.separator_fg,
+            &mut self.alternating_tint_bg,
+            &mut self.alternating_tint_fg,
+        ]
+    }
+}
+
+/// The separator drawn in front of a block. Besides the predefined shapes, any
+/// text can be used as separator.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Separator {
+    None,
+    PowerlineLeft,
+    PowerlineRight,
+    RoundedLeft,
+    RoundedRight,
+    SlantedLeft,
+    SlantedRight,
+    Text(String),
+}
+
+impl Separator {
+    pub fn glyph(&self) -> Option<&str> {
+        use self::Separator::*;
+        match *self {
+            None => Option::None,
+            PowerlineLeft => Some("\u{e0b2}"),
+            PowerlineRight => Some("\u{e0b0}"),
+            RoundedLeft => Some("\u{e0b6}"),
+            RoundedRight => Some("\u{e0b4}"),
+            SlantedLeft => Some("\u{e0be}"),
+            SlantedRight => Some("\u{e0bc}"),
+            Text(ref text) => Some(text),
+        }
+    }
+
+    /// Whether the filled part of the glyph faces the following block. For
+    /// these, `auto` colors are taken from the following block for the
+    /// foreground and from the preceding one for the background, and the
+    /// other way round for separators pointing to the right.
+    pub fn points_left(&self) -> bool {
+        use self::Separator::*;
+        match *self {
+            PowerlineRight | RoundedRight | SlantedRight => false,
+            _ => true,
+        }
+    }
+}
+
+impl FromStr for Separator {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        use self::Separator::*;
+        Ok(match s {
+            "none" => None,
+            "powerline-left" => PowerlineLeft,
+            "powerline-right" => PowerlineRight,
+            "rounded-left" => RoundedLeft,
+            "rounded-right" => RoundedRight,
+            "slanted-left" => SlantedLeft,
+            "slanted-right" => SlantedRight,
+            text => Text(text.to_owned()),
+        })
+    }
+}
+
+/// A handle to the theme in use, shared by all widgets of a block. Setting a new
+/// theme through any clone of the handle restyles every widget on the next redraw.
+#[derive(Debug, Clone)]
+pub struct SharedTheme(Arc<RwLock<Theme>>);
+
+impl SharedTheme {
+    pub fn new(theme: Theme) -> Self {
+        SharedTheme(Arc::new(RwLock::new(theme)))
+    }
+
+    pub fn get(&self) -> RwLockReadGuard<Theme> {
+        self.0.read().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn set(&self, theme: Theme) {
+        let mut current = self.0.write().unwrap_or_else(|poisoned| poisoned.into_inner());
+        *current = theme;
     }
 }
 
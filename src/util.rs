@@ -1,5 +1,6 @@
 use block::Block;
-use config::Config;
+use color::Color;
+use config::{Config, SharedBlockConfig};
 use errors::*;
 use std::collections::HashMap;
 use serde::de::DeserializeOwned;
@@ -11,8 +12,8 @@ use std::fmt::Display;
 use std::fs::{File, OpenOptions};
 use std::io::BufReader;
 use std::io::prelude::*;
-use std::num::ParseIntError;
 use std::path::Path;
+use themes::Separator;
 
 pub fn deserialize_file<T>(file: &str) -> Result<T>
 where
@@ -86,105 +87,178 @@ macro_rules! map_to_owned (
 
 struct PrintState {
     pub last_bg: Option<String>,
-    pub has_predecessor: bool,
+    pub merge: bool,
 }
 
 impl PrintState {
     fn set_last_bg(&mut self, bg: String) {
         self.last_bg = Some(bg);
     }
-    fn set_predecessor(&mut self, pre: bool) {
-        self.has_predecessor = pre;
+    fn set_merge(&mut self, merge: bool) {
+        self.merge = merge;
     }
 }
 
-pub fn print_blocks(order: &[String], block_map: &HashMap<String, &mut Block>, config: &Config) -> Result<()> {
+fn background_of(rendered: &Value) -> Result<String> {
+    Ok(rendered["background"]
+        .as_str()
+        .internal_error("util", "couldn't get background color")?
+        .to_owned())
+}
+
+pub fn print_blocks<W: Write>(
+    out: &mut W,
+    order: &[(String, SharedBlockConfig)],
+    block_map: &HashMap<String, &mut Block>,
+    config: &Config,
+) -> Result<()> {
     let mut state = PrintState {
-        has_predecessor: false,
         last_bg: None,
+        merge: false,
     };
+    let theme = config.theme.get();
+    let spacing = theme
+        .separator_block_width
+        .parse::<u32>()
+        .configuration_error("invalid separator_block_width")?;
 
-    print!("[");
-    for block_id in order {
+    let mut output: Vec<Value> = Vec::new();
+    for &(ref block_id, ref shared) in order {
         let block = &(*(block_map
             .get(block_id)
             .internal_error("util", "couldn't get block by id")?));
         let widgets = block.view();
         if widgets.is_empty() {
+            // Merging carries over a hidden block only if it merges as well
+            let merge = state.merge && shared.merge_with_next;
+            state.set_merge(merge);
             continue;
         }
-        let first = widgets[0];
-        let color = first.get_rendered()["background"]
-            .as_str()
-            .internal_error("util", "couldn't get background color")?;
+        let rendered: Vec<Value> = widgets.iter().map(|widget| widget.get_rendered()).collect();
+        let color = background_of(&rendered[0])?;
 
-        let sep_fg = if config.theme.separator_fg == "auto" {
-            color
+        // A block merged into its predecessor never gets a separator
+        let separator = if state.merge {
+            Separator::None
         } else {
-            &config.theme.separator_fg
+            shared
+                .separator
+                .as_ref()
+                .unwrap_or(&theme.separator)
+                .parse::<Separator>()
+                .configuration_error("invalid separator")?
         };
 
-        let sep_bg = if config.theme.separator_bg == "auto" {
-            state.last_bg.clone()
-        } else {
-            Some(config.theme.separator_bg.clone())
-        };
+        if let Some(glyph) = separator.glyph() {
+            let last_bg = match state.last_bg {
+                Some(ref bg) => Value::String(bg.clone()),
+                None => Value::Null,
+            };
+            let (auto_fg, auto_bg) = if separator.points_left() {
+                (Value::String(color.clone()), last_bg)
+            } else {
+                (last_bg, Value::String(color.clone()))
+            };
+
+            let sep_fg = match theme.separator_fg.parse::<Color>() {
+                Ok(Color::Auto) => auto_fg,
+                _ => Value::String(theme.separator_fg.clone()),
+            };
+
+            let sep_bg = match theme.separator_bg.parse::<Color>() {
+                Ok(Color::Auto) => auto_bg,
+                _ => Value::String(theme.separator_bg.clone()),
+            };
+
+            if let Some(previous) = output.last_mut() {
+                previous["separator_block_width"] = json!(spacing);
+            }
+            output.push(json!({
+                "full_text": glyph,
+                "separator": false,
+                "separator_block_width": spacing,
+                "background": sep_bg,
+                "color": sep_fg,
+                "markup": "pango"
+            }));
+        } else if let Some(previous) = output.last_mut() {
+            previous["separator_block_width"] = json!(0);
+        }
 
-        let separator = json!({
-                    "full_text": config.theme.separator,
-                  //"full_text": format!("<span font=\"DejaVu Sans Mono\">{}</span>", config.theme.separator),
-                    "separator": false,
-                    "separator_block_width": 0,
-                    "background": if sep_bg.is_some() { Value::String(sep_bg.unwrap()) } else { Value::Null },
-                    "color": sep_fg,
-                    "markup": "pango"
-                });
-        print!("{}{},", if state.has_predecessor { "," } else { "" },
-               separator.to_string());
-        print!("{}", first.to_string());
-        state.set_last_bg(color.to_owned());
-        state.set_predecessor(true);
-
-        for widget in widgets.iter().skip(1) {
-            print!("{}{}", if state.has_predecessor { "," } else { "" },
-                   widget.to_string());
-            state.set_last_bg(String::from(
-                widget.get_rendered()["background"]
-                    .as_str()
-                    .internal_error("util", "couldn't get background color")?,
-            ));
-            state.set_predecessor(true);
+        for widget in rendered {
+            state.set_last_bg(background_of(&widget)?);
+            output.push(widget);
         }
+        state.set_merge(shared.merge_with_next);
     }
-    println!("],");
+
+    writeln!(out, "{},", Value::Array(output).to_string())
+        .and_then(|_| out.flush())
+        .internal_error("util", "failed to write blocks")?;
 
     Ok(())
 }
 
-pub fn color_from_rgba(color: &str) -> ::std::result::Result<(u8, u8, u8, u8), ParseIntError> {
-    Ok((
-        u8::from_str_radix(&color[1..3], 16)?,
-        u8::from_str_radix(&color[3..5], 16)?,
-        u8::from_str_radix(&color[5..7], 16)?,
-        u8::from_str_radix(&color.get(7..9).unwrap_or("FF"), 16)?,
-    ))
-}
+/// Picks one of a list of icons for a value between 0 and 100, e.g. a battery level.
+///
+/// Without breakpoints, the range is split evenly between the icons. Otherwise
+/// `breakpoints` holds the lowest value of each icon but the first one.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct IconRamp {
+    /// Names of the icons, from the lowest to the highest value.
+    pub icons: Vec<String>,
 
-pub fn color_to_rgba(color: (u8, u8, u8, u8)) -> String {
-    format!("#{:02X}{:02X}{:02X}{:02X}", color.0, color.1, color.2, color.3)
+    #[serde(default)]
+    pub breakpoints: Option<Vec<u64>>,
 }
 
-// TODO: Allow for other non-additive tints
-pub fn add_colors(a: &str, b: &str) -> ::std::result::Result<String, ParseIntError> {
-    let (r_a, g_a, b_a, a_a) = color_from_rgba(a)?;
-    let (r_b, g_b, b_b, a_b) = color_from_rgba(b)?;
-
-    Ok(color_to_rgba((
-        r_a.checked_add(r_b).unwrap_or(255),
-        g_a.checked_add(g_b).unwrap_or(255),
-        b_a.checked_add(b_b).unwrap_or(255),
-        a_a.checked_add(a_b).unwrap_or(255),
-    )))
+impl IconRamp {
+    pub fn new(icons: &[&str]) -> Self {
+        IconRamp {
+            icons: icons.iter().map(|&icon| icon.to_owned()).collect(),
+            breakpoints: None,
+        }
+    }
+
+    pub fn with_breakpoints(mut self, breakpoints: &[u64]) -> Self {
+        self.breakpoints = Some(breakpoints.to_vec());
+        self
+    }
+
+    /// Checks the ramp, reporting errors for the given block.
+    pub fn validate(&self, block: &str) -> Result<()> {
+        if self.icons.is_empty() {
+            return Err(BlockError(block.to_owned(), "icon_ramp needs at least one icon".to_owned()));
+        }
+        if let Some(ref breakpoints) = self.breakpoints {
+            if breakpoints.len() + 1 != self.icons.len() {
+                return Err(BlockError(
+                    block.to_owned(),
+                    "icon_ramp needs exactly one breakpoint less than icons".to_owned(),
+                ));
+            }
+            if breakpoints.windows(2).any(|pair| pair[0] >= pair[1]) {
+                return Err(BlockError(
+                    block.to_owned(),
+                    "icon_ramp breakpoints have to be strictly ascending".to_owned(),
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the name of the icon for `value`. Values above 100 get the last icon.
+    pub fn icon(&self, value: u64) -> &str {
+        if self.icons.is_empty() {
+            return "";
+        }
+        let index = match self.breakpoints {
+            Some(ref breakpoints) => breakpoints.iter().take_while(|&&breakpoint| value >= breakpoint).count(),
+            None => value.min(100) as usize * self.icons.len() / 100,
+        };
+        &self.icons[index.min(self.icons.len() - 1)]
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -308,3 +382,31 @@ macro_rules! mapped_struct {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn icon_ramp_splits_evenly_without_breakpoints() {
+        let ramp = IconRamp::new(&["low", "mid", "high"]);
+        assert_eq!(ramp.icon(0), "low");
+        assert_eq!(ramp.icon(33), "low");
+        assert_eq!(ramp.icon(34), "mid");
+        assert_eq!(ramp.icon(67), "high");
+        assert_eq!(ramp.icon(100), "high");
+        assert_eq!(ramp.icon(u64::max_value()), "high");
+    }
+
+    #[test]
+    fn icon_ramp_starts_icons_at_breakpoints() {
+        let ramp = IconRamp::new(&["low", "mid", "high"]).with_breakpoints(&[21, 71]);
+        assert_eq!(ramp.icon(20), "low");
+        assert_eq!(ramp.icon(21), "mid");
+        assert_eq!(ramp.icon(70), "mid");
+        assert_eq!(ramp.icon(71), "high");
+        assert_eq!(ramp.icon(u64::max_value()), "high");
+        assert!(ramp.validate("test").is_ok());
+        assert!(ramp.with_breakpoints(&[50]).validate("test").is_err());
+    }
+}
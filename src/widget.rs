@@ -1,7 +1,8 @@
 use themes::Theme;
 use serde_json::value::Value;
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum State {
     Idle,
     Info,
@@ -25,5 +26,5 @@ impl State {
 
 pub trait I3BarWidget {
     fn to_string(&self) -> String;
-    fn get_rendered(&self) -> &Value;
+    fn get_rendered(&self) -> Value;
 }
@@ -9,8 +9,6 @@ pub struct ButtonWidget {
     icon: Option<String>,
     state: State,
     id: String,
-    rendered: Value,
-    cached_output: Option<String>,
     config: Config,
 }
 
@@ -21,61 +19,53 @@ impl ButtonWidget {
             icon: None,
             state: State::Idle,
             id: String::from(id),
-            rendered: json!({
-                "full_text": "",
-                "separator": false,
-                "separator_block_width": 0,
-                "background": "#000000",
-                "color": "#000000"
-            }),
             config,
-            cached_output: None,
         }
     }
 
     pub fn with_icon(mut self, name: &str) -> Self {
         self.icon = self.config.icons.get(name).cloned();
-        self.update();
         self
     }
 
     pub fn with_content(mut self, content: Option<String>) -> Self {
         self.content = content;
-        self.update();
         self
     }
 
     pub fn with_text(mut self, content: &str) -> Self {
         self.content = Some(String::from(content));
-        self.update();
         self
     }
 
     pub fn with_state(mut self, state: State) -> Self {
         self.state = state;
-        self.update();
         self
     }
 
     pub fn set_text<S: Into<String>>(&mut self, content: S) {
         self.content = Some(content.into());
-        self.update();
     }
 
     pub fn set_icon(&mut self, name: &str) {
         self.icon = self.config.icons.get(name).cloned();
-        self.update();
     }
 
     pub fn set_state(&mut self, state: State) {
         self.state = state;
-        self.update();
     }
+}
 
-    fn update(&mut self) {
-        let (key_bg, key_fg) = self.state.theme_keys(&self.config.theme);
+impl I3BarWidget for ButtonWidget {
+    fn to_string(&self) -> String {
+        self.get_rendered().to_string()
+    }
 
-        self.rendered = json!({
+    fn get_rendered(&self) -> Value {
+        let theme = self.config.theme.get();
+        let (key_bg, key_fg) = self.state.theme_keys(&theme);
+
+        json!({
             "full_text": format!("{}{} ",
                                 self.icon.clone().unwrap_or_else(|| String::from(" ")),
                                 self.content.clone().unwrap_or_else(|| String::from(""))),
@@ -84,20 +74,6 @@ impl ButtonWidget {
             "separator_block_width": 0,
             "background": key_bg,
             "color": key_fg
-        });
-
-        self.cached_output = Some(self.rendered.to_string());
-    }
-}
-
-impl I3BarWidget for ButtonWidget {
-    fn to_string(&self) -> String {
-        self.cached_output
-            .clone()
-            .unwrap_or_else(|| self.rendered.to_string())
-    }
-
-    fn get_rendered(&self) -> &Value {
-        &self.rendered
+        })
     }
 }
@@ -14,8 +14,7 @@ pub struct RotatingTextWidget {
     content: String,
     icon: Option<String>,
     state: State,
-    rendered: Value,
-    cached_output: Option<String>,
+    name: Option<String>,
     config: Config,
     pub rotating: bool,
 }
@@ -32,14 +31,7 @@ impl RotatingTextWidget {
             content: String::new(),
             icon: None,
             state: State::Idle,
-            rendered: json!({
-                "full_text": "",
-                "separator": false,
-                "separator_block_width": 0,
-                "background": "#000000",
-                "color": "#000000"
-            }),
-            cached_output: None,
+            name: None,
             config,
             rotating: false,
         }
@@ -47,13 +39,17 @@ impl RotatingTextWidget {
 
     pub fn with_icon(mut self, name: &str) -> Self {
         self.icon = self.config.icons.get(name).cloned();
-        self.update();
         self
     }
 
     pub fn with_state(mut self, state: State) -> Self {
         self.state = state;
-        self.update();
+        self
+    }
+
+    /// Names the widget, so that clicks on it can be told apart.
+    pub fn with_name(mut self, name: &str) -> Self {
+        self.name = Some(String::from(name));
         self
     }
 
@@ -65,18 +61,15 @@ impl RotatingTextWidget {
         } else {
             self.next_rotation = None;
         }
-        self.update();
         self
     }
 
     pub fn set_state(&mut self, state: State) {
         self.state = state;
-        self.update();
     }
 
     pub fn set_icon(&mut self, name: &str) {
         self.icon = self.config.icons.get(name).cloned();
-        self.update();
     }
 
     pub fn set_text(&mut self, content: String) {
@@ -89,7 +82,6 @@ impl RotatingTextWidget {
                 self.next_rotation = None;
             }
         }
-        self.update()
     }
 
     pub fn is_empty(&self) -> bool {
@@ -121,24 +113,6 @@ impl RotatingTextWidget {
         }
     }
 
-    fn update(&mut self) {
-        let (key_bg, key_fg) = self.state.theme_keys(&self.config.theme);
-
-        self.rendered = json!({
-            "full_text": format!("{}{} ",
-                                self.icon.clone().unwrap_or_else(|| String::from(" ")),
-                                self.get_rotated_content()),
-            "separator": false,
-            "separator_block_width": 0,
-            "min_width": if self.content == "" {"".to_string()} else {"0".repeat(self.width+5)},
-            "align": "left",
-            "background": key_bg,
-            "color": key_fg
-        });
-
-        self.cached_output = Some(self.rendered.to_string());
-    }
-
     pub fn next(&mut self) -> Result<(bool, Option<Duration>)> {
         if let Some(next_rotation) = self.next_rotation {
             let now = Instant::now();
@@ -148,13 +122,11 @@ impl RotatingTextWidget {
                 if self.rotation_pos < self.content.len() {
                     self.rotation_pos += 1;
                     self.next_rotation = Some(now + self.rotation_speed);
-                    self.update();
                     Ok((true, Some(self.rotation_speed)))
                 } else {
                     self.rotation_pos = 0;
                     self.rotating = false;
                     self.next_rotation = Some(now + self.rotation_interval);
-                    self.update();
                     Ok((true, Some(self.rotation_interval)))
                 }
             } else {
@@ -169,12 +141,27 @@ impl RotatingTextWidget {
 
 impl I3BarWidget for RotatingTextWidget {
     fn to_string(&self) -> String {
-        self.cached_output
-            .clone()
-            .unwrap_or_else(|| self.rendered.to_string())
+        self.get_rendered().to_string()
     }
 
-    fn get_rendered(&self) -> &Value {
-        &self.rendered
+    fn get_rendered(&self) -> Value {
+        let theme = self.config.theme.get();
+        let (key_bg, key_fg) = self.state.theme_keys(&theme);
+
+        let mut rendered = json!({
+            "full_text": format!("{}{} ",
+                                self.icon.clone().unwrap_or_else(|| String::from(" ")),
+                                self.get_rotated_content()),
+            "separator": false,
+            "separator_block_width": 0,
+            "min_width": if self.content == "" {"".to_string()} else {"0".repeat(self.width+5)},
+            "align": "left",
+            "background": key_bg,
+            "color": key_fg
+        });
+        if let Some(ref name) = self.name {
+            rendered["name"] = json!(name);
+        }
+        rendered
     }
 }